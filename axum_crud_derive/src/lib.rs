@@ -0,0 +1,1154 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr, Path, Type};
+
+/// Derives `Database`, `DatabaseFetchAll`, `Include` and, when a field is marked with
+/// `#[crud(parent = Type)]`, `MatchParent` for the annotated struct.
+///
+/// `deleted_at = "..."` names a nullable bigint column that `delete` sets instead of removing the
+/// row, hiding it from every lookup until it is restored.
+///
+/// The parent can be embedded with `?include=parent`, and the types listed in
+/// `children(...)` with `?include=<their table>`. Deleting a row first applies the policy
+/// of each child type, `Child = restrict` (the default), `cascade` or `set_null`.
+///
+/// Every field not marked `skip` is a column in `COLUMNS`, its SQL type taken from the
+/// field type's `ColumnType` impl.
+///
+/// `full_text_table = "..."` (SQLite FTS5) and `full_text_column = "..."` (Postgres
+/// `tsvector`) switch search to the full-text index over the `search_text` fields. The
+/// `schema` subcommand prints it with the triggers or generated column keeping it current.
+///
+/// ```ignore
+/// #[derive(Crud)]
+/// #[crud(table = "sub_dummy", id = "id_sub_dummy", default_order = "-name")]
+/// pub struct SubDummy {
+///     #[crud(search_numeric, order)]
+///     pub id_sub_dummy: i64,
+///     #[crud(parent = Dummy)]
+///     pub id_dummy: i64,
+///     #[crud(search_text, order, filter(eq, like), select)]
+///     pub name: String,
+///     #[crud(skip)]
+///     pub is_valid: Option<bool>,
+/// }
+/// ```
+#[proc_macro_derive(Crud, attributes(crud))]
+pub fn derive_crud(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct Column {
+    ident: Ident,
+    search_text: bool,
+    search_numeric: bool,
+    search_float: bool,
+    order: bool,
+    select: bool,
+    ty: Type,
+    /// `FilterOp` variants listed in `#[crud(filter(...))]`, empty for the defaults of the
+    /// field type, `None` when not filterable.
+    filter: Option<Vec<&'static str>>,
+    parent: Option<Path>,
+}
+
+/// A type declared in `#[crud(children(...))]` and what deleting its parent does to it.
+struct Child {
+    ty: Path,
+    on_delete: OnDelete,
+}
+
+enum OnDelete {
+    /// Fails with a conflict while there are children.
+    Restrict,
+    /// Deletes the children one by one, so each of them is checked and has its own children
+    /// handled first.
+    Cascade,
+    /// Detaches the children, whose parent column has to be nullable.
+    SetNull,
+}
+
+const FILTER_OPS: &[(&str, &str)] = &[
+    ("eq", "Eq"),
+    ("ne", "Ne"),
+    ("gt", "Gt"),
+    ("gte", "Gte"),
+    ("lt", "Lt"),
+    ("lte", "Lte"),
+    ("like", "Like"),
+    ("in", "In"),
+];
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+
+    let mut table = None;
+    let mut id = None;
+    let mut default_order = None;
+    let mut full_text_table = None;
+    let mut full_text_column = None;
+    let mut deleted_at = None;
+    let mut children = vec![];
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("crud")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("id") {
+                id = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("default_order") {
+                default_order = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else if meta.path.is_ident("full_text_table") {
+                full_text_table = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else if meta.path.is_ident("full_text_column") {
+                full_text_column = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else if meta.path.is_ident("deleted_at") {
+                deleted_at = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("children") {
+                meta.parse_nested_meta(|child| {
+                    let policy = match child.input.peek(syn::Token![=]) {
+                        true => child.value()?.parse::<Ident>()?.to_string(),
+                        false => "restrict".to_string(),
+                    };
+                    let on_delete = match policy.as_str() {
+                        "restrict" => OnDelete::Restrict,
+                        "cascade" => OnDelete::Cascade,
+                        "set_null" => OnDelete::SetNull,
+                        _ => return Err(child.error("unsupported delete policy")),
+                    };
+                    children.push(Child {
+                        ty: child.path,
+                        on_delete,
+                    });
+                    Ok(())
+                })
+            } else {
+                Err(meta.error("unsupported crud attribute"))
+            }
+        })?;
+    }
+
+    let Some(table) = table else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "missing #[crud(table = \"...\")] attribute",
+        ));
+    };
+    let Some(id) = id else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "missing #[crud(id = \"...\")] attribute",
+        ));
+    };
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "Crud can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "Crud can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut columns = vec![];
+    for field in &fields.named {
+        let mut column = Column {
+            ident: field.ident.clone().unwrap(),
+            search_text: false,
+            search_numeric: false,
+            search_float: false,
+            order: false,
+            select: false,
+            ty: field.ty.clone(),
+            filter: None,
+            parent: None,
+        };
+        let mut skip = false;
+
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("crud")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("search_text") {
+                    column.search_text = true;
+                } else if meta.path.is_ident("search_numeric") {
+                    column.search_numeric = true;
+                } else if meta.path.is_ident("search_float") {
+                    column.search_float = true;
+                } else if meta.path.is_ident("order") {
+                    column.order = true;
+                } else if meta.path.is_ident("select") {
+                    column.select = true;
+                } else if meta.path.is_ident("filter") {
+                    let mut ops = vec![];
+                    if meta.input.peek(syn::token::Paren) {
+                        meta.parse_nested_meta(|op| {
+                            let name = op.path.get_ident().map(|i| i.to_string());
+                            match FILTER_OPS.iter().find(|(n, _)| Some(*n) == name.as_deref()) {
+                                Some((_, variant)) => ops.push(*variant),
+                                None => return Err(op.error("unsupported filter operator")),
+                            }
+                            Ok(())
+                        })?;
+                    }
+                    column.filter = Some(ops);
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                } else if meta.path.is_ident("parent") {
+                    column.parent = Some(meta.value()?.parse::<Path>()?);
+                } else {
+                    return Err(meta.error("unsupported crud field attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        if !skip {
+            columns.push(column);
+        }
+    }
+
+    let Some(id_column) = columns.iter().find(|c| c.ident == id) else {
+        return Err(syn::Error::new_spanned(
+            name,
+            format!("id column `{id}` is not a field of the struct"),
+        ));
+    };
+    let id_ident = &id_column.ident;
+
+    let mut parents = columns.iter().filter(|c| c.parent.is_some());
+    let parent = parents.next();
+    if let Some(extra) = parents.next() {
+        return Err(syn::Error::new_spanned(
+            &extra.ident,
+            "only one field can be marked as parent",
+        ));
+    }
+
+    if let Some(order) = &default_order {
+        let value = order.value();
+        let unknown = value
+            .split(',')
+            .map(|key| key.trim().trim_start_matches('-'))
+            .map(|key| key.split(':').next().unwrap_or_default())
+            .find(|key| !columns.iter().any(|c| c.order && c.ident == key));
+
+        if let Some(key) = unknown {
+            return Err(syn::Error::new_spanned(
+                order,
+                format!("default order key `{key}` is not an order column"),
+            ));
+        }
+    }
+
+    // Condition keeping soft deleted rows out of single row lookups.
+    let live = deleted_at
+        .as_ref()
+        .map(|column| format!(" AND {column} IS NULL"))
+        .unwrap_or_default();
+
+    let database = expand_database(
+        &table,
+        &id,
+        id_ident,
+        &columns,
+        deleted_at.as_deref(),
+        &live,
+        &children,
+    );
+    let schema = expand_columns(&id, &columns, deleted_at.as_deref());
+    let consts = [
+        ("DEFAULT_ORDER", default_order),
+        ("FULL_TEXT_TABLE", full_text_table),
+        ("FULL_TEXT_COLUMN", full_text_column),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| {
+        let name = format_ident!("{name}");
+        let value = value?;
+        Some(quote! { const #name: &'static str = #value; })
+    })
+    .collect::<TokenStream>();
+
+    let fetch_all = expand_fetch_all(&table, &id, &live, parent, &columns, consts);
+    let match_parent =
+        parent.map(|parent| expand_match_parent(name, &table, &id, deleted_at.as_deref(), parent));
+    let include = expand_include(&id, parent, &children);
+
+    Ok(quote! {
+        impl crate::prelude::Database<crate::router::Db> for #name {
+            #schema
+            #database
+        }
+
+        impl crate::prelude::DatabaseFetchAll<crate::router::Db> for #name {
+            #fetch_all
+        }
+
+        impl crate::prelude::Include<crate::router::Db> for #name {
+            #include
+        }
+
+        #match_parent
+    })
+}
+
+/// Rewrites `?` placeholders as `$1..$n` for Postgres.
+fn numbered(sql: &str) -> String {
+    let mut count = 0;
+
+    sql.chars()
+        .map(|c| {
+            if c == '?' {
+                count += 1;
+                format!("${count}")
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Expression choosing between the `?` and `$n` flavour of `sql` from a `dialect` in scope.
+fn dialect_sql(sql: &str) -> TokenStream {
+    let postgres = numbered(sql);
+
+    quote! {
+        match dialect {
+            crate::dialect::Dialect::Postgres => #postgres,
+            _ => #sql,
+        }
+    }
+}
+
+fn expand_database(
+    table: &str,
+    id: &str,
+    id_ident: &Ident,
+    columns: &[Column],
+    deleted_at: Option<&str>,
+    live: &str,
+    children: &[Child],
+) -> TokenStream {
+    let names = columns
+        .iter()
+        .map(|c| c.ident.to_string())
+        .collect::<Vec<_>>();
+    // MySQL has no RETURNING, its insert reports the id through the query result instead.
+    let mysql_insert_sql = format!(
+        "INSERT INTO {table} ({}) VALUES ({})",
+        names.join(", "),
+        vec!["?"; names.len()].join(", ")
+    );
+    let insert_sql = format!("{mysql_insert_sql} RETURNING {id}");
+    let insert_fields = columns.iter().map(|c| &c.ident).collect::<Vec<_>>();
+
+    let update_columns = columns.iter().filter(|c| c.ident != id).collect::<Vec<_>>();
+    let update_sql = format!(
+        "UPDATE {table} SET {} WHERE {id} = ?{live}",
+        update_columns
+            .iter()
+            .map(|c| format!("{} = ?", c.ident))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let update_fields = update_columns.iter().map(|c| &c.ident);
+
+    let delete_sql = match deleted_at {
+        Some(column) => {
+            format!("UPDATE {table} SET {column} = ? WHERE {id} = ?{live}")
+        }
+        None => format!("DELETE FROM {table} WHERE {id} = ?"),
+    };
+    // The soft delete stamps the row with the time, bound so every dialect gets the same type.
+    let deleted_now = deleted_at.map(|_| quote! { .bind(crate::prelude::epoch()) });
+    let fetch_one_sql = format!("SELECT * FROM {table} WHERE {id} = ?{live}");
+    let trash = deleted_at.map(|column| expand_trash(table, id, column));
+    let delete_children = (!children.is_empty()).then(|| {
+        let policies = children.iter().map(expand_on_delete);
+        quote! {
+            async fn delete_children(
+                conn: &mut <crate::router::Db as sqlx::Database>::Connection,
+                id: i64,
+                trash: bool,
+            ) -> Result<(), crate::error::CrudError> {
+                #(#policies)*
+                Ok(())
+            }
+        }
+    });
+
+    let insert_sql = dialect_sql(&insert_sql);
+    let update_sql = dialect_sql(&update_sql);
+    let delete_sql = dialect_sql(&delete_sql);
+    let fetch_one_sql = dialect_sql(&fetch_one_sql);
+
+    quote! {
+        const TABLE: &'static str = #table;
+        const FIELD_ID: &'static str = #id;
+
+        #trash
+        #delete_children
+
+        fn get_id(&self) -> i64 {
+            self.#id_ident
+        }
+
+        async fn insert(&self, db: impl crate::prelude::Connect<crate::router::Db>) -> Result<i64, crate::error::CrudError> {
+            use sqlx::Row;
+
+            let mut conn = db.connect().await?;
+            let dialect = crate::dialect::Dialect::from_connection(&conn);
+
+            if dialect == crate::dialect::Dialect::MySql {
+                let result = sqlx::query(#mysql_insert_sql)
+                    #(.bind(&self.#insert_fields))*
+                    .execute(&mut *conn)
+                    .await?;
+
+                // LAST_INSERT_ID() is 0 unless the column generated the id itself.
+                return Ok(match result.last_insert_id() {
+                    Some(id) if id != 0 => id,
+                    _ => self.#id_ident,
+                });
+            }
+
+            let row = sqlx::query(#insert_sql)
+                #(.bind(&self.#insert_fields))*
+                .fetch_one(&mut *conn)
+                .await?;
+
+            Ok(row.try_get(0)?)
+        }
+
+        async fn update(&self, db: impl crate::prelude::Connect<crate::router::Db>) -> Result<(), crate::error::CrudError> {
+            let mut conn = db.connect().await?;
+            let dialect = crate::dialect::Dialect::from_connection(&conn);
+            sqlx::query(#update_sql)
+                #(.bind(&self.#update_fields))*
+                .bind(&self.#id_ident)
+                .execute(&mut *conn)
+                .await?;
+
+            Ok(())
+        }
+
+        async fn delete(db: impl crate::prelude::Connect<crate::router::Db>, id: i64) -> Result<(), crate::error::CrudError> {
+            let mut conn = db.connect().await?;
+            let dialect = crate::dialect::Dialect::from_connection(&conn);
+            sqlx::query(#delete_sql)
+                #deleted_now
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+
+            Ok(())
+        }
+
+        async fn fetch_one(db: impl crate::prelude::Connect<crate::router::Db>, id: i64) -> Result<Self, crate::error::CrudError> {
+            let mut conn = db.connect().await?;
+            let dialect = crate::dialect::Dialect::from_connection(&conn);
+            Ok(sqlx::query_as(#fetch_one_sql)
+                .bind(id)
+                .fetch_one(&mut *conn)
+                .await?)
+        }
+    }
+}
+
+/// `COLUMNS`, typed through `ColumnType`, with the soft delete column last when no field
+/// holds it.
+fn expand_columns(id: &str, columns: &[Column], deleted_at: Option<&str>) -> TokenStream {
+    let mut definitions = columns
+        .iter()
+        .map(|column| {
+            let name = column.ident.to_string();
+            let ty = &column.ty;
+            let primary_key = name == id;
+            let references = match &column.parent {
+                Some(parent) => quote! {
+                    Some(crate::prelude::Reference {
+                        table: <#parent as crate::prelude::Database<crate::router::Db>>::TABLE,
+                        column: <#parent as crate::prelude::Database<crate::router::Db>>::FIELD_ID,
+                    })
+                },
+                None => quote! { None },
+            };
+
+            quote! {
+                crate::prelude::Column {
+                    name: #name,
+                    sql_type: <#ty as crate::prelude::ColumnType>::SQL_TYPE,
+                    nullable: <#ty as crate::prelude::ColumnType>::NULLABLE,
+                    primary_key: #primary_key,
+                    references: #references,
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(name) = deleted_at.filter(|name| !columns.iter().any(|c| c.ident == name)) {
+        definitions.push(quote! {
+            crate::prelude::Column {
+                name: #name,
+                sql_type: crate::prelude::SqlType::BigInt,
+                nullable: true,
+                primary_key: false,
+                references: None,
+            }
+        });
+    }
+
+    quote! {
+        const COLUMNS: &'static [crate::prelude::Column] = &[#(#definitions),*];
+    }
+}
+
+/// Statements applying the delete policy of `child` to the children of the row `id`.
+fn expand_on_delete(child: &Child) -> TokenStream {
+    let ty = &child.ty;
+    let database = quote! { <#ty as crate::prelude::Database<crate::router::Db>> };
+    let fetch_all = quote! { <#ty as crate::prelude::DatabaseFetchAll<crate::router::Db>> };
+
+    match child.on_delete {
+        OnDelete::Restrict => quote! {
+            let trashed = match trash {
+                true => crate::prelude::Trashed::Without,
+                false => crate::prelude::Trashed::With,
+            };
+            if !#fetch_all::fetch_in(&mut *conn, #fetch_all::FIELD_PARENT, &[id], Some(1), trashed).await?.is_empty() {
+                return Err(crate::error::CrudError::ForeignKeyViolation(format!(
+                    "{} rows still reference {}",
+                    #database::TABLE,
+                    id
+                )));
+            }
+        },
+        // The children go for good whatever their own soft delete, they cannot outlive the row.
+        OnDelete::Cascade => quote! {
+            if !trash {
+                for child in #fetch_all::fetch_in(&mut *conn, #fetch_all::FIELD_PARENT, &[id], None, crate::prelude::Trashed::With).await? {
+                    <#ty as crate::prelude::Check>::check_delete(&child).map_err(crate::error::CrudError::from)?;
+
+                    #database::delete_children(&mut *conn, #database::get_id(&child), false).await?;
+                    sqlx::query(&format!(
+                        "DELETE FROM {} WHERE {} = {}",
+                        #database::TABLE,
+                        #database::FIELD_ID,
+                        crate::dialect::Dialect::from_connection(conn).placeholders().bind()
+                    ))
+                    .bind(#database::get_id(&child))
+                    .execute(&mut *conn)
+                    .await?;
+                }
+            }
+        },
+        OnDelete::SetNull => quote! {
+            if !trash {
+                sqlx::query(&format!(
+                    "UPDATE {} SET {} = NULL WHERE {} = {}",
+                    #database::TABLE,
+                    #fetch_all::FIELD_PARENT,
+                    #fetch_all::FIELD_PARENT,
+                    crate::dialect::Dialect::from_connection(conn).placeholders().bind()
+                ))
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+            }
+        },
+    }
+}
+
+/// Soft delete column and the statements reaching the rows it marks.
+fn expand_trash(table: &str, id: &str, deleted_at: &str) -> TokenStream {
+    let restore_sql = dialect_sql(&format!(
+        "UPDATE {table} SET {deleted_at} = NULL WHERE {id} = ? AND {deleted_at} IS NOT NULL"
+    ));
+    let purge_sql = dialect_sql(&format!(
+        "DELETE FROM {table} WHERE {id} = ? AND {deleted_at} IS NOT NULL"
+    ));
+    let fetch_trashed_sql = dialect_sql(&format!(
+        "SELECT * FROM {table} WHERE {id} = ? AND {deleted_at} IS NOT NULL"
+    ));
+
+    quote! {
+        const FIELD_DELETED_AT: &'static str = #deleted_at;
+
+        async fn fetch_trashed(db: impl crate::prelude::Connect<crate::router::Db>, id: i64) -> Result<Self, crate::error::CrudError> {
+            let mut conn = db.connect().await?;
+            let dialect = crate::dialect::Dialect::from_connection(&conn);
+            Ok(sqlx::query_as(#fetch_trashed_sql)
+                .bind(id)
+                .fetch_one(&mut *conn)
+                .await?)
+        }
+
+        async fn restore(db: impl crate::prelude::Connect<crate::router::Db>, id: i64) -> Result<(), crate::error::CrudError> {
+            let mut conn = db.connect().await?;
+            let dialect = crate::dialect::Dialect::from_connection(&conn);
+            let result = sqlx::query(#restore_sql)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+
+            match result.rows_affected() {
+                0 => Err(crate::error::CrudError::NotFound),
+                _ => Ok(()),
+            }
+        }
+
+        async fn purge(db: impl crate::prelude::Connect<crate::router::Db>, id: i64) -> Result<(), crate::error::CrudError> {
+            let mut conn = db.connect().await?;
+            let dialect = crate::dialect::Dialect::from_connection(&conn);
+            let result = sqlx::query(#purge_sql)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+
+            match result.rows_affected() {
+                0 => Err(crate::error::CrudError::NotFound),
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+fn expand_fetch_all(
+    table: &str,
+    id: &str,
+    live: &str,
+    parent: Option<&Column>,
+    columns: &[Column],
+    consts: TokenStream,
+) -> TokenStream {
+    let fields = |f: fn(&Column) -> bool| {
+        columns
+            .iter()
+            .filter(|c| f(c))
+            .map(|c| c.ident.to_string())
+            .collect::<Vec<_>>()
+    };
+    let fields_text = fields(|c| c.search_text);
+    let fields_numeric = fields(|c| c.search_numeric);
+    let fields_float = fields(|c| c.search_float);
+    let fields_order = fields(|c| c.order);
+
+    let fields_filter = columns.iter().filter_map(|c| {
+        let name = c.ident.to_string();
+        let ty = &c.ty;
+        let ops = c.filter.as_ref()?;
+        let ops = if ops.is_empty() {
+            quote! { <#ty as crate::prelude::FilterValue>::OPS }
+        } else {
+            let ops = ops.iter().map(|op| format_ident!("{op}"));
+            quote! { &[#(crate::prelude::FilterOp::#ops),*] }
+        };
+        Some(quote! {
+            crate::prelude::FilterField {
+                name: #name,
+                parse: <#ty as crate::prelude::FilterValue>::parse,
+                ops: #ops,
+            }
+        })
+    });
+
+    let fields_select = columns.iter().filter(|c| c.select).map(|c| {
+        let name = c.ident.to_string();
+        let ty = &c.ty;
+        quote! {
+            crate::prelude::SelectField {
+                name: #name,
+                decode: crate::prelude::decode_field::<#ty>,
+            }
+        }
+    });
+
+    let field_parent = parent.map(|parent| {
+        let field = parent.ident.to_string();
+        quote! { const FIELD_PARENT: &'static str = #field; }
+    });
+    let bind_parent = parent.map(|_| {
+        quote! { query = query.bind(list.parent_id.unwrap_or_default()); }
+    });
+
+    let fetch_one_sql = format!("SELECT {{}} FROM {table} WHERE {id} = {{}}{live}");
+    let count_sql = format!("SELECT count(*) FROM {table} {{}}");
+
+    quote! {
+        #field_parent
+
+        const FIELDS_TEXT: &'static [&'static str] = &[#(#fields_text),*];
+        const FIELDS_NUMERIC: &'static [&'static str] = &[#(#fields_numeric),*];
+        const FIELDS_FLOAT: &'static [&'static str] = &[#(#fields_float),*];
+
+        const FIELDS_FILTER: &'static [crate::prelude::FilterField] = &[#(#fields_filter),*];
+
+        const FIELDS_ORDER: &'static [&'static str] = &[#(#fields_order),*];
+        #consts
+
+        const FIELDS_SELECT: &'static [crate::prelude::SelectField] = &[#(#fields_select),*];
+
+        async fn fetch_all(
+            db: impl crate::prelude::Connect<crate::router::Db>,
+            list: &crate::prelude::ListQuery,
+        ) -> Result<Vec<Self>, crate::error::CrudError> {
+            use crate::prelude::{BindToken, DatabaseFetchAll, Dialect};
+
+            let mut conn = db.connect().await?;
+            let dialect = Dialect::from_connection(&conn);
+            let sql = format!("SELECT * {}", Self::create_query_list(list, &mut dialect.placeholders()));
+
+            let query = Self::fill_query_list(list, dialect, sqlx::query_as(&sql), BindToken::bind_token);
+            Ok(query.fetch_all(&mut *conn).await?)
+        }
+
+        async fn fetch_in(
+            db: impl crate::prelude::Connect<crate::router::Db>,
+            field: &str,
+            ids: &[i64],
+            limit: Option<i64>,
+            trashed: crate::prelude::Trashed,
+        ) -> Result<Vec<Self>, crate::error::CrudError> {
+            use crate::prelude::{DatabaseFetchAll, Dialect};
+
+            if ids.is_empty() {
+                return Ok(vec![]);
+            }
+
+            let mut conn = db.connect().await?;
+            let dialect = Dialect::from_connection(&conn);
+            let sql = format!("SELECT * {}", Self::create_query_in(field, ids.len(), limit, trashed, &mut dialect.placeholders()));
+
+            let mut query = sqlx::query_as(&sql);
+            for id in ids {
+                query = query.bind(*id);
+            }
+            Ok(query.fetch_all(&mut *conn).await?)
+        }
+
+        async fn fetch_all_fields(
+            db: impl crate::prelude::Connect<crate::router::Db>,
+            list: &crate::prelude::ListQuery,
+            fields: &[crate::prelude::SelectField],
+        ) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, crate::error::CrudError> {
+            use crate::prelude::{BindToken, DatabaseFetchAll, Dialect};
+
+            let mut conn = db.connect().await?;
+            let dialect = Dialect::from_connection(&conn);
+            let columns = fields.iter().map(|f| f.name).collect::<Vec<_>>().join(", ");
+            let sql = format!("SELECT {} {}", columns, Self::create_query_list(list, &mut dialect.placeholders()));
+
+            let query = Self::fill_query_list(list, dialect, sqlx::query(&sql), BindToken::bind_token);
+            let rows = query.fetch_all(&mut *conn).await?;
+
+            rows.iter().map(|row| crate::prelude::select_row(row, fields)).collect()
+        }
+
+        async fn fetch_one_fields(
+            db: impl crate::prelude::Connect<crate::router::Db>,
+            id: i64,
+            fields: &[crate::prelude::SelectField],
+        ) -> Result<serde_json::Map<String, serde_json::Value>, crate::error::CrudError> {
+            use crate::prelude::Dialect;
+
+            let mut conn = db.connect().await?;
+            let dialect = Dialect::from_connection(&conn);
+            let columns = fields.iter().map(|f| f.name).collect::<Vec<_>>().join(", ");
+            let sql = format!(#fetch_one_sql, columns, dialect.placeholders().bind());
+
+            let row = sqlx::query(&sql).bind(id).fetch_one(&mut *conn).await?;
+
+            crate::prelude::select_row(&row, fields)
+        }
+
+        async fn count_all(
+            db: impl crate::prelude::Connect<crate::router::Db>,
+            list: &crate::prelude::ListQuery,
+        ) -> Result<i64, crate::error::CrudError> {
+            use crate::prelude::{BindToken, DatabaseFetchAll, Dialect};
+
+            let mut conn = db.connect().await?;
+            let dialect = Dialect::from_connection(&conn);
+            let mut placeholders = dialect.placeholders();
+
+            let sql_where = Self::create_query_where(&list.search, &list.filters, list.trashed, None, &mut placeholders).unwrap_or_default();
+            let sql = format!(#count_sql, sql_where);
+
+            let mut query = sqlx::query_scalar(&sql);
+            #bind_parent
+            query = Self::fill_query_where(&list.search, dialect, query, BindToken::bind_token);
+            query = Self::fill_query_filter(&list.filters, query, BindToken::bind_token);
+            Ok(query.fetch_one(&mut *conn).await?)
+        }
+    }
+}
+
+/// Empty without a parent or children, leaving the defaults that include nothing.
+fn expand_include(id: &str, parent: Option<&Column>, children: &[Child]) -> TokenStream {
+    if parent.is_none() && children.is_empty() {
+        return TokenStream::new();
+    }
+
+    let children = children.iter().map(|child| &child.ty).collect::<Vec<_>>();
+
+    let parent_field = parent.map(|parent| {
+        let field = parent.ident.to_string();
+        quote! {
+            crate::prelude::IncludeField {
+                name: crate::include::PARENT,
+                field: #field,
+            },
+        }
+    });
+    let parent_include = parent.map(|parent| {
+        let field = parent.ident.to_string();
+        let parent_type = parent.parent.as_ref().unwrap();
+        quote! {
+            if name == crate::include::PARENT {
+                return crate::include::parent::<#parent_type>(db, #field, rows).await;
+            }
+        }
+    });
+
+    quote! {
+        const INCLUDES: &'static [crate::prelude::IncludeField] = &[
+            #parent_field
+            #(crate::prelude::IncludeField {
+                name: <#children as crate::prelude::Database<crate::router::Db>>::TABLE,
+                field: #id,
+            },)*
+        ];
+
+        async fn include(
+            db: impl crate::prelude::Connect<crate::router::Db>,
+            name: &str,
+            rows: &mut [serde_json::Map<String, serde_json::Value>],
+        ) -> Result<(), crate::error::CrudError> {
+            #parent_include
+            #(if name == <#children as crate::prelude::Database<crate::router::Db>>::TABLE {
+                return crate::include::children::<#children, Self>(db, #id, rows).await;
+            })*
+            Ok(())
+        }
+    }
+}
+
+fn expand_match_parent(
+    name: &Ident,
+    table: &str,
+    id: &str,
+    deleted_at: Option<&str>,
+    parent: &Column,
+) -> TokenStream {
+    let field = &parent.ident;
+    let parent_type = parent.parent.as_ref().unwrap();
+
+    // A nullable parent column, as `set_null` children have, reads as no parent's id.
+    let parent_id = match &parent.ty {
+        Type::Path(ty) if ty.path.segments.last().is_some_and(|s| s.ident == "Option") => {
+            quote! { self.#field.unwrap_or_default() }
+        }
+        _ => quote! { self.#field },
+    };
+
+    // The parent's own soft delete column is only known from its impl, so it goes last.
+    let live = deleted_at
+        .map(|column| format!(" AND a.{column} IS NULL"))
+        .unwrap_or_default();
+    let sql = format!(
+        "SELECT b.* FROM {table} a INNER JOIN {{}} b ON a.{field} = b.{{}} WHERE a.{field} = ? AND a.{id} = ?{live}{{}}"
+    );
+    let postgres = numbered(&sql);
+
+    quote! {
+        impl crate::prelude::MatchParent<crate::router::Db> for #name {
+            type Parent = #parent_type;
+
+            async fn fetch_parent(
+                db: impl crate::prelude::Connect<crate::router::Db>,
+                parent_id: i64,
+                id: i64,
+            ) -> Result<Self::Parent, crate::error::CrudError> {
+                use crate::prelude::Database;
+
+                let mut conn = db.connect().await?;
+                let table = <#parent_type as Database<crate::router::Db>>::TABLE;
+                let field_id = <#parent_type as Database<crate::router::Db>>::FIELD_ID;
+                let live = match <#parent_type as Database<crate::router::Db>>::FIELD_DELETED_AT {
+                    "" => String::new(),
+                    deleted_at => format!(" AND b.{deleted_at} IS NULL"),
+                };
+                let sql = match crate::dialect::Dialect::from_connection(&conn) {
+                    crate::dialect::Dialect::Postgres => format!(#postgres, table, field_id, live),
+                    _ => format!(#sql, table, field_id, live),
+                };
+
+                Ok(sqlx::query_as(&sql)
+                    .bind(parent_id)
+                    .bind(id)
+                    .fetch_one(&mut *conn)
+                    .await?)
+            }
+
+            fn get_parent_id(&mut self) -> i64 {
+                #parent_id
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn expand_insert_columns() {
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "sub_dummy", id = "id_sub_dummy")]
+            struct SubDummy {
+                id_sub_dummy: i64,
+                name: String,
+                #[crud(parent = Dummy)]
+                id_dummy: i64,
+                #[crud(skip)]
+                is_valid: Option<bool>,
+            }
+        };
+
+        let tokens = expand(input).unwrap().to_string();
+
+        assert!(tokens.contains(
+            "\"INSERT INTO sub_dummy (id_sub_dummy, name, id_dummy) VALUES (?, ?, ?) RETURNING id_sub_dummy\""
+        ));
+        assert!(tokens.contains(
+            "\"INSERT INTO sub_dummy (id_sub_dummy, name, id_dummy) VALUES ($1, $2, $3) RETURNING id_sub_dummy\""
+        ));
+        assert!(tokens
+            .contains("\"INSERT INTO sub_dummy (id_sub_dummy, name, id_dummy) VALUES (?, ?, ?)\""));
+        assert!(tokens.contains("last_insert_id"));
+        assert!(tokens
+            .contains("\"UPDATE sub_dummy SET name = ?, id_dummy = ? WHERE id_sub_dummy = ?\""));
+        assert!(tokens.contains("MatchParent"));
+    }
+
+    #[test]
+    fn expand_search_fields() {
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "dummy", id = "id_dummy")]
+            struct Dummy {
+                #[crud(search_numeric, order)]
+                id_dummy: i64,
+                #[crud(search_text, order, select)]
+                name: String,
+            }
+        };
+
+        let tokens = expand(input).unwrap().to_string();
+
+        assert!(tokens
+            .contains("name : \"name\" , decode : crate :: prelude :: decode_field :: < String >"));
+        assert!(!tokens.contains("name : \"id_dummy\" , decode"));
+        assert!(tokens.contains("FIELDS_TEXT : & 'static [& 'static str] = & [\"name\"]"));
+        assert!(tokens.contains("FIELDS_NUMERIC : & 'static [& 'static str] = & [\"id_dummy\"]"));
+        assert!(tokens.contains("FIELDS_FLOAT : & 'static [& 'static str] = & []"));
+        assert!(tokens
+            .contains("FIELDS_ORDER : & 'static [& 'static str] = & [\"id_dummy\" , \"name\"]"));
+        assert!(!tokens.contains("MatchParent"));
+    }
+
+    #[test]
+    fn expand_filter_fields() {
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "dummy", id = "id_dummy")]
+            struct Dummy {
+                #[crud(filter(gte, lt, in))]
+                id_dummy: i64,
+                #[crud(filter)]
+                is_valid: Option<bool>,
+                name: String,
+            }
+        };
+
+        let tokens = expand(input).unwrap().to_string();
+
+        assert!(tokens.contains("name : \"id_dummy\" , parse : < i64 as crate :: prelude :: FilterValue > :: parse , ops : & [crate :: prelude :: FilterOp :: Gte , crate :: prelude :: FilterOp :: Lt , crate :: prelude :: FilterOp :: In]"));
+        assert!(tokens.contains("name : \"is_valid\" , parse : < Option < bool > as crate :: prelude :: FilterValue > :: parse , ops : < Option < bool > as crate :: prelude :: FilterValue > :: OPS"));
+        assert!(!tokens.contains("name : \"name\" , parse"));
+    }
+
+    #[test]
+    fn expand_includes() {
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "dummy", id = "id_dummy", children(SubDummy = cascade, Other))]
+            struct Dummy {
+                id_dummy: i64,
+                #[crud(parent = Group)]
+                id_group: i64,
+            }
+        };
+
+        let tokens = expand(input).unwrap().to_string();
+
+        assert!(tokens.contains("name : crate :: include :: PARENT , field : \"id_group\""));
+        assert!(
+            tokens.contains("crate :: include :: parent :: < Group > (db , \"id_group\" , rows)")
+        );
+        assert!(tokens.contains(
+            "crate :: include :: children :: < SubDummy , Self > (db , \"id_dummy\" , rows)"
+        ));
+        assert!(tokens.contains("crate :: include :: children :: < Other , Self >"));
+        assert!(tokens.contains("for child in < SubDummy as crate :: prelude :: DatabaseFetchAll < crate :: router :: Db >> :: fetch_in (& mut * conn , < SubDummy as crate :: prelude :: DatabaseFetchAll < crate :: router :: Db >> :: FIELD_PARENT , & [id] , None , crate :: prelude :: Trashed :: With) . await ?"));
+        assert!(tokens.contains("if ! < Other as crate :: prelude :: DatabaseFetchAll < crate :: router :: Db >> :: fetch_in (& mut * conn , < Other as crate :: prelude :: DatabaseFetchAll < crate :: router :: Db >> :: FIELD_PARENT , & [id] , Some (1) , trashed) . await ? . is_empty ()"));
+
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "dummy", id = "id_dummy")]
+            struct Dummy {
+                id_dummy: i64,
+            }
+        };
+
+        let tokens = expand(input).unwrap().to_string();
+
+        assert!(!tokens.contains("INCLUDES"));
+    }
+
+    #[test]
+    fn expand_deleted_at() {
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "sub_dummy", id = "id_sub_dummy", deleted_at = "deleted_at")]
+            struct SubDummy {
+                id_sub_dummy: i64,
+                #[crud(parent = Dummy)]
+                id_dummy: i64,
+            }
+        };
+
+        let tokens = expand(input).unwrap().to_string();
+
+        assert!(tokens.contains("const FIELD_DELETED_AT : & 'static str = \"deleted_at\""));
+        assert!(tokens.contains(
+            "\"UPDATE sub_dummy SET deleted_at = ? WHERE id_sub_dummy = ? AND deleted_at IS NULL\""
+        ));
+        assert!(tokens.contains(
+            "\"SELECT * FROM sub_dummy WHERE id_sub_dummy = $1 AND deleted_at IS NULL\""
+        ));
+        assert!(tokens.contains(
+            "\"DELETE FROM sub_dummy WHERE id_sub_dummy = ? AND deleted_at IS NOT NULL\""
+        ));
+        assert!(tokens.contains("a.id_sub_dummy = ? AND a.deleted_at IS NULL{}"));
+        assert!(!tokens.contains("DELETE FROM sub_dummy WHERE id_sub_dummy = ?\""));
+        assert!(tokens.contains(". bind (crate :: prelude :: epoch ()) . bind (id)"));
+    }
+
+    #[test]
+    fn expand_columns_with_parent() {
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "sub_dummy", id = "id_sub_dummy", deleted_at = "deleted_at")]
+            struct SubDummy {
+                id_sub_dummy: i64,
+                #[crud(parent = Dummy)]
+                id_dummy: Option<i64>,
+                #[crud(skip)]
+                is_valid: Option<bool>,
+            }
+        };
+
+        let tokens = expand(input).unwrap().to_string();
+
+        assert!(tokens.contains("name : \"id_sub_dummy\" , sql_type : < i64 as crate :: prelude :: ColumnType > :: SQL_TYPE , nullable : < i64 as crate :: prelude :: ColumnType > :: NULLABLE , primary_key : true , references : None"));
+        assert!(tokens.contains("name : \"id_dummy\" , sql_type : < Option < i64 > as crate :: prelude :: ColumnType > :: SQL_TYPE"));
+        assert!(tokens.contains("primary_key : false , references : Some (crate :: prelude :: Reference { table : < Dummy as crate :: prelude :: Database < crate :: router :: Db >> :: TABLE"));
+        assert!(tokens.contains("name : \"deleted_at\" , sql_type : crate :: prelude :: SqlType :: BigInt , nullable : true"));
+        assert!(!tokens.contains("name : \"is_valid\""));
+    }
+
+    #[test]
+    fn expand_default_order() {
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "dummy", id = "id_dummy", default_order = "-name:nulls_last")]
+            struct Dummy {
+                #[crud(order)]
+                id_dummy: i64,
+                #[crud(order)]
+                name: String,
+            }
+        };
+
+        let tokens = expand(input).unwrap().to_string();
+
+        assert!(tokens.contains("const DEFAULT_ORDER : & 'static str = \"-name:nulls_last\""));
+
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "dummy", id = "id_dummy", default_order = "name")]
+            struct Dummy {
+                id_dummy: i64,
+                name: String,
+            }
+        };
+
+        assert!(expand(input).is_err());
+    }
+
+    #[test]
+    fn expand_unknown_filter_op() {
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "dummy", id = "id_dummy")]
+            struct Dummy {
+                #[crud(filter(between))]
+                id_dummy: i64,
+            }
+        };
+
+        assert!(expand(input).is_err());
+    }
+
+    #[test]
+    fn expand_unknown_delete_policy() {
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "dummy", id = "id_dummy", children(SubDummy = orphan))]
+            struct Dummy {
+                id_dummy: i64,
+            }
+        };
+
+        assert!(expand(input).is_err());
+    }
+
+    #[test]
+    fn expand_missing_table() {
+        let input: DeriveInput = parse_quote! {
+            #[crud(id = "id_dummy")]
+            struct Dummy {
+                id_dummy: i64,
+            }
+        };
+
+        assert!(expand(input).is_err());
+    }
+
+    #[test]
+    fn expand_unknown_id() {
+        let input: DeriveInput = parse_quote! {
+            #[crud(table = "dummy", id = "id")]
+            struct Dummy {
+                id_dummy: i64,
+            }
+        };
+
+        assert!(expand(input).is_err());
+    }
+}
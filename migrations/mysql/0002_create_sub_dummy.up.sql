@@ -0,0 +1,6 @@
+CREATE TABLE sub_dummy (
+    id_sub_dummy bigint PRIMARY KEY,
+    name varchar(255) NOT NULL,
+    id_dummy bigint NOT NULL,
+    FOREIGN KEY (id_dummy) REFERENCES dummy (id_dummy)
+);
@@ -0,0 +1,5 @@
+CREATE TABLE dummy (
+    id_dummy bigint PRIMARY KEY,
+    name text NOT NULL,
+    deleted_at bigint
+);
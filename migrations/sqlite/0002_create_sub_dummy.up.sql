@@ -0,0 +1,7 @@
+CREATE TABLE sub_dummy (
+    id_sub_dummy bigint PRIMARY KEY,
+    name text NOT NULL,
+    id_dummy bigint NOT NULL REFERENCES dummy (id_dummy)
+);
+
+CREATE INDEX sub_dummy_id_dummy ON sub_dummy (id_dummy);
@@ -0,0 +1,559 @@
+use std::{future::Future, marker::PhantomData};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{AnyConnection, Connection};
+use validator::Validate;
+
+use crate::{
+    error::Problem,
+    prelude::*,
+    router::{Db, Pool},
+};
+
+#[derive(Deserialize, Default, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkMode {
+    /// Every item is applied or none is.
+    #[default]
+    Atomic,
+    /// Failed items are rolled back individually, the rest is committed.
+    Partial,
+}
+
+#[derive(Deserialize)]
+pub struct BulkParams {
+    #[serde(default)]
+    mode: BulkMode,
+}
+
+#[derive(Serialize)]
+pub struct BulkResult {
+    pub index: usize,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Problem>,
+}
+
+const MAX_ITEMS: usize = 1000;
+
+/// One item of a bulk request, applied on the connection of the enclosing transaction.
+trait Operation {
+    fn apply(
+        self,
+        conn: &mut AnyConnection,
+    ) -> impl Future<Output = Result<(StatusCode, i64), CrudError>>;
+}
+
+struct Create<T>(T);
+
+struct Update<T>(T);
+
+struct Delete<T>(i64, PhantomData<fn() -> T>);
+
+impl<T> Operation for Create<T>
+where
+    T: Database<Db> + Validate + Check,
+{
+    async fn apply(self, conn: &mut AnyConnection) -> Result<(StatusCode, i64), CrudError> {
+        let Create(mut new) = self;
+        new.validate()?;
+        new.check_create().map_err(CrudError::from)?;
+
+        let id = new.insert(&mut *conn).await?;
+        Ok((StatusCode::CREATED, id))
+    }
+}
+
+impl<T> Operation for Update<T>
+where
+    T: Database<Db> + Validate + Check,
+{
+    async fn apply(self, conn: &mut AnyConnection) -> Result<(StatusCode, i64), CrudError> {
+        let Update(mut new) = self;
+        new.validate()?;
+
+        let id = new.get_id();
+        let old = T::fetch_one(&mut *conn, id).await?;
+        new.check_update(old).map_err(CrudError::from)?;
+
+        new.update(&mut *conn).await?;
+        Ok((StatusCode::OK, id))
+    }
+}
+
+impl<T> Operation for Delete<T>
+where
+    T: Database<Db> + Check,
+{
+    async fn apply(self, conn: &mut AnyConnection) -> Result<(StatusCode, i64), CrudError> {
+        let Delete(id, _) = self;
+        let old = T::fetch_one(&mut *conn, id).await?;
+        old.check_delete().map_err(CrudError::from)?;
+
+        T::delete_children(&mut *conn, id, !T::FIELD_DELETED_AT.is_empty()).await?;
+        T::delete(&mut *conn, id).await?;
+        Ok((StatusCode::NO_CONTENT, id))
+    }
+}
+
+async fn bulk<O>(pool: &Pool, mode: BulkMode, operations: Vec<O>) -> Response
+where
+    O: Operation,
+{
+    if operations.is_empty() || operations.len() > MAX_ITEMS {
+        return Problem::new(StatusCode::BAD_REQUEST)
+            .detail(format!("Between 1 and {MAX_ITEMS} items are accepted"))
+            .into_response();
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return CrudError::from(e).into_response(),
+    };
+
+    let mut results = vec![];
+    let mut failed = None;
+
+    for (index, operation) in operations.into_iter().enumerate() {
+        let result = match mode {
+            BulkMode::Atomic => operation.apply(&mut tx).await,
+            BulkMode::Partial => match tx.begin().await {
+                Ok(mut savepoint) => match operation.apply(&mut savepoint).await {
+                    Ok(v) => savepoint.commit().await.map(|_| v).map_err(CrudError::from),
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(CrudError::from(e)),
+            },
+        };
+
+        results.push(match result {
+            Ok((status, id)) => BulkResult {
+                index,
+                status: status.as_u16(),
+                id: Some(id),
+                error: None,
+            },
+            Err(e) => {
+                failed.get_or_insert(e.status());
+                BulkResult {
+                    index,
+                    status: e.status().as_u16(),
+                    id: None,
+                    error: Some(e.problem()),
+                }
+            }
+        });
+
+        if mode == BulkMode::Atomic && failed.is_some() {
+            break;
+        }
+    }
+
+    match (mode, failed) {
+        (BulkMode::Atomic, Some(status)) => {
+            if let Err(e) = tx.rollback().await {
+                return CrudError::from(e).into_response();
+            }
+
+            for result in results.iter_mut().filter(|r| r.error.is_none()) {
+                result.status = StatusCode::FAILED_DEPENDENCY.as_u16();
+                result.id = None;
+            }
+
+            (status, Json(results)).into_response()
+        }
+        _ => {
+            if let Err(e) = tx.commit().await {
+                return CrudError::from(e).into_response();
+            }
+
+            let status = match mode {
+                BulkMode::Atomic => StatusCode::OK,
+                BulkMode::Partial => StatusCode::MULTI_STATUS,
+            };
+
+            (status, Json(results)).into_response()
+        }
+    }
+}
+
+pub async fn bulk_create<T>(
+    State(pool): State<Pool>,
+    Query(params): Query<BulkParams>,
+    Json(items): Json<Vec<T>>,
+) -> Response
+where
+    T: Database<Db> + Validate + Check,
+{
+    let operations = items.into_iter().map(Create).collect();
+
+    bulk(&pool, params.mode, operations).await
+}
+
+pub async fn bulk_update<T>(
+    State(pool): State<Pool>,
+    Query(params): Query<BulkParams>,
+    Json(items): Json<Vec<T>>,
+) -> Response
+where
+    T: Database<Db> + Validate + Check,
+{
+    let operations = items.into_iter().map(Update).collect();
+
+    bulk(&pool, params.mode, operations).await
+}
+
+pub async fn bulk_delete<T>(
+    State(pool): State<Pool>,
+    Query(params): Query<BulkParams>,
+    Json(ids): Json<Vec<i64>>,
+) -> Response
+where
+    T: Database<Db> + Check,
+{
+    let operations = ids
+        .into_iter()
+        .map(|id| Delete::<T>(id, PhantomData))
+        .collect();
+
+    bulk(&pool, params.mode, operations).await
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        http::{self, Request, StatusCode},
+        routing::{delete, post, put},
+        Router,
+    };
+
+    use crate::{prelude::*, types::dummy::Dummy};
+    use http_body_util::BodyExt;
+    use serde_json::{json, Value};
+    use sqlx::{any::AnyPoolOptions, Any, Pool};
+    use tower::ServiceExt;
+
+    async fn database(size: i64) -> Pool<Any> {
+        sqlx::any::install_default_drivers();
+        let pool = AnyPoolOptions::new()
+            .max_connections(1) // needs to be 1, otherwise memory database is gone
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        crate::migrate::up(&pool).await.unwrap();
+
+        for i in 1..=size {
+            let _ = Dummy::insert(
+                &(Dummy {
+                    id_dummy: i,
+                    name: format!("name-{}", i),
+                    is_valid: Some(true),
+                }),
+                &pool,
+            )
+            .await;
+        }
+
+        pool
+    }
+
+    async fn router(pool: Pool<Any>) -> axum::Router {
+        Router::new()
+            .route("/dummy/bulk", post(super::bulk_create::<Dummy>))
+            .route("/dummy/bulk", put(super::bulk_update::<Dummy>))
+            .route("/dummy/bulk", delete(super::bulk_delete::<Dummy>))
+            .with_state(pool)
+    }
+
+    #[tokio::test]
+    async fn bulk_create_ok() {
+        let pool = database(0).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!([{"id_dummy": 1, "name": "name-1"}, {"id_dummy": 2, "name": "name-2"}])
+            .to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/dummy/bulk")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let results: Vec<Value> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|r| r["status"].clone())
+                .collect::<Vec<_>>(),
+            [201, 201]
+        );
+        assert_eq!(
+            Dummy::count_all(&pool, &ListQuery::default())
+                .await
+                .unwrap(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn bulk_create_atomic_conflict() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!([{"id_dummy": 2, "name": "name-2"}, {"id_dummy": 1, "name": "name-1"}])
+            .to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/dummy/bulk")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let results: Vec<Value> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(results[0]["status"], 424);
+        assert_eq!(results[1]["status"], 409);
+        assert_eq!(results[1]["error"]["status"], 409);
+        assert!(Dummy::fetch_one(&pool, 2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn bulk_create_atomic_invalid() {
+        let pool = database(0).await;
+
+        let app = router(pool.clone()).await;
+
+        let body =
+            json!([{"id_dummy": 1, "name": "name-1"}, {"id_dummy": 2, "name": ""}]).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/dummy/bulk")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let results: Vec<Value> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(results[1]["error"]["errors"]["name"][0], "length");
+        assert_eq!(
+            Dummy::count_all(&pool, &ListQuery::default())
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn bulk_create_partial() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!([{"id_dummy": 1, "name": "name-1"}, {"id_dummy": 2, "name": "name-2"}])
+            .to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/dummy/bulk?mode=partial")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let results: Vec<Value> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(results[0]["status"], 409);
+        assert_eq!(results[1]["status"], 201);
+        assert_eq!(results[1]["id"], 2);
+        assert!(Dummy::fetch_one(&pool, 2).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn bulk_create_empty() {
+        let pool = database(0).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!([]).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/dummy/bulk")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn bulk_update_ok() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body =
+            json!([{"id_dummy": 1, "name": "name-new-1"}, {"id_dummy": 2, "name": "name-new-2"}])
+                .to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/bulk")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        assert_eq!(Dummy::fetch_one(&pool, 1).await.unwrap().name, "name-new-1");
+        assert_eq!(Dummy::fetch_one(&pool, 2).await.unwrap().name, "name-new-2");
+    }
+
+    #[tokio::test]
+    async fn bulk_update_inexistent() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body =
+            json!([{"id_dummy": 1, "name": "name-new-1"}, {"id_dummy": 2, "name": "name-new-2"}])
+                .to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/bulk")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        assert_eq!(Dummy::fetch_one(&pool, 1).await.unwrap().name, "name-1");
+    }
+
+    #[tokio::test]
+    async fn bulk_delete_ok() {
+        let pool = database(3).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!([1, 2]).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::DELETE)
+                    .uri("/dummy/bulk")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        assert_eq!(
+            Dummy::count_all(&pool, &ListQuery::default())
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn bulk_delete_partial() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!([1, 2]).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::DELETE)
+                    .uri("/dummy/bulk?mode=partial")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let results: Vec<Value> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(results[0]["status"], 204);
+        assert_eq!(results[1]["status"], 404);
+        assert_eq!(
+            Dummy::count_all(&pool, &ListQuery::default())
+                .await
+                .unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,771 @@
+use std::{collections::BTreeMap, env, error::Error, fmt, fs, io, net::SocketAddr, time::Duration};
+
+use axum::http::{HeaderName, HeaderValue, Method};
+use serde::Deserialize;
+use sqlx::any::AnyPoolOptions;
+use tower_http::cors::{AllowHeaders, AllowOrigin, CorsLayer};
+
+use crate::{
+    list::{Limits, DEFAULT_LIMIT, MAX_LIMIT},
+    schema::Table,
+};
+
+/// Variable naming the configuration file, read only when present unless set.
+pub const ENV_FILE: &str = "AXUM_CRUD_CONFIG";
+pub const DEFAULT_FILE: &str = "axum_crud.toml";
+
+/// Prefix of the variables overriding a setting, sections split by `__`:
+/// `AXUM_CRUD_DATABASE__MAX_CONNECTIONS=10` sets `database.max_connections`. Values are read
+/// as the type of the setting, lists comma separated. Variables matching no setting are
+/// ignored, unlike unknown keys in the file.
+pub const ENV_PREFIX: &str = "AXUM_CRUD_";
+
+/// Headers a cross origin client may read, besides the safelisted ones.
+const EXPOSED_HEADERS: [&str; 7] = [
+    "etag",
+    "link",
+    "x-item-id",
+    "x-paging-maxlimit",
+    "x-paging-next",
+    "x-paging-size",
+    "x-paging-total",
+];
+
+/// Server settings, read from the TOML file and then overridden by the environment.
+///
+/// Every setting has a default, so an empty file (or none) is valid as long as the database
+/// URL comes from somewhere.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub paging: PagingConfig,
+    pub cors: CorsConfig,
+    pub log: LogConfig,
+    pub health: HealthConfig,
+    pub features: Features,
+    /// Variables with the override prefix that match no setting, left for the caller to
+    /// warn about once logging is up.
+    #[serde(skip)]
+    pub ignored_env: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: SocketAddr,
+    /// How long in-flight requests may run once shutdown begins, zero dropping them at once.
+    pub drain_timeout_secs: u64,
+    /// How long requests are still accepted after the shutdown signal, with the readiness
+    /// probe already failing, so load balancers stop routing here first.
+    pub pre_stop_delay_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            drain_timeout_secs: 30,
+            pre_stop_delay_secs: 0,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_secs)
+    }
+
+    pub fn pre_stop_delay(&self) -> Duration {
+        Duration::from_secs(self.pre_stop_delay_secs)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// Also read from `DATABASE_URL`.
+    pub url: Option<String>,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_secs: u64,
+    /// Zero keeps idle connections open.
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 600,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn pool_options(&self) -> AnyPoolOptions {
+        AnyPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_secs))
+            .idle_timeout(
+                (self.idle_timeout_secs > 0).then(|| Duration::from_secs(self.idle_timeout_secs)),
+            )
+    }
+}
+
+/// Page sizes of the list endpoints, `resources.<table>` overriding them for one resource.
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(default, deny_unknown_fields)]
+pub struct PagingConfig {
+    pub default_limit: i64,
+    pub max_limit: i64,
+    pub resources: BTreeMap<String, ResourcePaging>,
+}
+
+impl Default for PagingConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: DEFAULT_LIMIT,
+            max_limit: MAX_LIMIT,
+            resources: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(deny_unknown_fields)]
+pub struct ResourcePaging {
+    pub default_limit: Option<i64>,
+    pub max_limit: Option<i64>,
+}
+
+impl PagingConfig {
+    pub fn limits(&self, table: &str) -> Limits {
+        let resource = self.resources.get(table).cloned().unwrap_or_default();
+
+        Limits {
+            default: resource.default_limit.unwrap_or(self.default_limit),
+            max: resource.max_limit.unwrap_or(self.max_limit),
+        }
+    }
+}
+
+/// Cross origin access, disabled while no origin is allowed. `"*"` allows any origin.
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    /// Zero leaves preflight responses uncached.
+    pub max_age_secs: u64,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec![],
+            allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE"]
+                .map(String::from)
+                .to_vec(),
+            max_age_secs: 0,
+        }
+    }
+}
+
+impl CorsConfig {
+    /// The layer enforcing these settings, `None` when disabled. Expects a validated config.
+    pub fn layer(&self) -> Option<CorsLayer> {
+        if self.allowed_origins.is_empty() {
+            return None;
+        }
+
+        let origins = match self.allowed_origins.iter().any(|origin| origin == "*") {
+            true => AllowOrigin::any(),
+            false => AllowOrigin::list(
+                self.allowed_origins
+                    .iter()
+                    .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+            ),
+        };
+        let methods = self
+            .allowed_methods
+            .iter()
+            .filter_map(|method| method.parse::<Method>().ok())
+            .collect::<Vec<_>>();
+
+        let mut layer = CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(AllowHeaders::mirror_request())
+            .expose_headers(EXPOSED_HEADERS.map(HeaderName::from_static));
+        if self.max_age_secs > 0 {
+            layer = layer.max_age(Duration::from_secs(self.max_age_secs));
+        }
+
+        Some(layer)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// One of `error`, `warn`, `info`, `debug` and `trace`.
+    pub level: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn level(&self) -> tracing::Level {
+        self.level.parse().unwrap_or(tracing::Level::INFO)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    /// Time each database check of `/readyz` and `/health` may take.
+    pub timeout_ms: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self { timeout_ms: 1000 }
+    }
+}
+
+impl HealthConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// Optional behaviour, switched on and off as a whole.
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    /// `/bulk` endpoints of the top level resources.
+    pub bulk: bool,
+    /// `/:id/restore` and `/:id/purge` endpoints of the soft deleted resources.
+    pub trash: bool,
+    /// Applies the pending migrations before serving, as `--migrate` does.
+    pub migrate: bool,
+    /// Refuses to serve when the live schema differs from the resources.
+    pub verify_schema: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Self {
+            bulk: true,
+            trash: true,
+            migrate: false,
+            verify_schema: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(String, io::Error),
+    Parse(String),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "cannot read configuration {path}: {e}"),
+            ConfigError::Parse(e) => write!(f, "invalid configuration: {e}"),
+            ConfigError::Invalid(problems) => {
+                write!(f, "invalid configuration:")?;
+                problems
+                    .iter()
+                    .try_for_each(|problem| write!(f, "\n  - {problem}"))
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl Config {
+    /// Reads the file named by `AXUM_CRUD_CONFIG`, or `axum_crud.toml` when there is one, and
+    /// applies the environment overrides.
+    pub fn load() -> Result<Self, ConfigError> {
+        let (path, required) = match env::var(ENV_FILE) {
+            Ok(path) => (path, true),
+            Err(_) => (DEFAULT_FILE.to_string(), false),
+        };
+
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) if !required && e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(ConfigError::Read(path, e)),
+        };
+
+        Self::parse(&source, env::vars())
+    }
+
+    fn parse(
+        source: &str,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, ConfigError> {
+        let mut table = source
+            .parse::<toml::Table>()
+            .map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+        // Checked before the overrides, so only the file has its unknown keys refused.
+        toml::Value::Table(table.clone())
+            .try_into::<Self>()
+            .map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+        let mut ignored_env = vec![];
+
+        for (key, value) in vars {
+            let path = match key.as_str() {
+                ENV_FILE => continue,
+                "DATABASE_URL" => vec!["database".to_string(), "url".to_string()],
+                _ => match key.strip_prefix(ENV_PREFIX) {
+                    Some(path) => path.split("__").map(str::to_lowercase).collect(),
+                    None => continue,
+                },
+            };
+
+            match Setting::at(&path) {
+                Some(setting) => set(&mut table, &key, &path, setting.parse(&key, &value)?)?,
+                None => ignored_env.push(key),
+            }
+        }
+
+        let mut config = toml::Value::Table(table)
+            .try_into::<Self>()
+            .map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        config.ignored_env = ignored_env;
+
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = vec![];
+
+        if self.database.max_connections == 0 {
+            problems.push("database.max_connections must be at least 1".to_string());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            problems.push("database.min_connections exceeds database.max_connections".to_string());
+        }
+
+        let limits = [("paging".to_string(), self.paging.limits(""))]
+            .into_iter()
+            .chain(self.paging.resources.keys().map(|table| {
+                (
+                    format!("paging.resources.{table}"),
+                    self.paging.limits(table),
+                )
+            }));
+        for (section, limits) in limits {
+            if limits.default < 1 {
+                problems.push(format!("{section}.default_limit must be at least 1"));
+            }
+            if limits.max < limits.default {
+                problems.push(format!("{section}.max_limit is below the default limit"));
+            }
+        }
+
+        let origins = &self.cors.allowed_origins;
+        if origins.len() > 1 && origins.iter().any(|origin| origin == "*") {
+            problems.push("cors.allowed_origins cannot mix \"*\" with other origins".to_string());
+        }
+        for origin in origins.iter().filter(|origin| *origin != "*") {
+            if HeaderValue::from_str(origin).is_err() {
+                problems.push(format!(
+                    "cors.allowed_origins has an invalid origin `{origin}`"
+                ));
+            }
+        }
+        for method in &self.cors.allowed_methods {
+            if method.parse::<Method>().is_err() {
+                problems.push(format!(
+                    "cors.allowed_methods has an invalid method `{method}`"
+                ));
+            }
+        }
+
+        if self.health.timeout_ms == 0 {
+            problems.push("health.timeout_ms must be at least 1".to_string());
+        }
+
+        if self.log.level.parse::<tracing::Level>().is_err() {
+            problems.push(format!(
+                "log.level `{}` is not one of error, warn, info, debug or trace",
+                self.log.level
+            ));
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigError::Invalid(problems)),
+        }
+    }
+
+    /// Fails for paging overrides of tables no registered resource uses.
+    pub fn check_resources(&self, tables: &[Table]) -> Result<(), ConfigError> {
+        let problems = self
+            .paging
+            .resources
+            .keys()
+            .filter(|name| !tables.iter().any(|table| table.name == name.as_str()))
+            .map(|name| format!("paging.resources.{name} is not a resource table"))
+            .collect::<Vec<_>>();
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigError::Invalid(problems)),
+        }
+    }
+}
+
+/// Kind of value a setting holds, deciding how an environment override of it is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    Text,
+    Integer,
+    Boolean,
+    List,
+}
+
+impl Setting {
+    /// The setting at `path`, `None` when there is none.
+    fn at(path: &[String]) -> Option<Self> {
+        let path = path.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let setting = match path.as_slice() {
+            ["server", "bind"] | ["database", "url"] | ["log", "level"] => Setting::Text,
+            ["server", "drain_timeout_secs" | "pre_stop_delay_secs"]
+            | ["database", "max_connections" | "min_connections"]
+            | ["database", "acquire_timeout_secs" | "idle_timeout_secs"]
+            | ["paging", "default_limit" | "max_limit"]
+            | ["paging", "resources", _, "default_limit" | "max_limit"]
+            | ["cors", "max_age_secs"]
+            | ["health", "timeout_ms"] => Setting::Integer,
+            ["features", "bulk" | "trash" | "migrate" | "verify_schema"] => Setting::Boolean,
+            ["cors", "allowed_origins" | "allowed_methods"] => Setting::List,
+            _ => return None,
+        };
+
+        Some(setting)
+    }
+
+    /// Reads `value`, the value of the variable `key`.
+    fn parse(self, key: &str, value: &str) -> Result<toml::Value, ConfigError> {
+        let invalid = |expected: &str| {
+            ConfigError::Invalid(vec![format!("{key} must be {expected}, found `{value}`")])
+        };
+
+        match self {
+            Setting::Text => Ok(toml::Value::String(value.to_string())),
+            Setting::Integer => value
+                .trim()
+                .parse()
+                .map(toml::Value::Integer)
+                .map_err(|_| invalid("an integer")),
+            Setting::Boolean => value
+                .trim()
+                .parse()
+                .map(toml::Value::Boolean)
+                .map_err(|_| invalid("true or false")),
+            Setting::List => Ok(toml::Value::Array(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| toml::Value::String(item.to_string()))
+                    .collect(),
+            )),
+        }
+    }
+}
+
+/// Sets the setting at `path` to `value`, read from the variable `key`.
+fn set(
+    table: &mut toml::Table,
+    key: &str,
+    path: &[String],
+    value: toml::Value,
+) -> Result<(), ConfigError> {
+    let Some((last, sections)) = path.split_last() else {
+        return Ok(());
+    };
+
+    let mut table = table;
+    for section in sections {
+        let entry = table
+            .entry(section.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+
+        table = match entry {
+            toml::Value::Table(table) => table,
+            _ => {
+                return Err(ConfigError::Invalid(vec![format!(
+                    "{key} overrides `{section}`, which is not a section"
+                )]))
+            }
+        };
+    }
+
+    table.insert(last.clone(), value);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn config_defaults() {
+        let config = Config::parse("", vec![]).unwrap();
+
+        assert_eq!(config.server.bind, "0.0.0.0:3000".parse().unwrap());
+        assert_eq!(config.server.drain_timeout(), Duration::from_secs(30));
+        assert_eq!(config.server.pre_stop_delay(), Duration::ZERO);
+        assert_eq!(config.database.url, None);
+        assert_eq!(config.database.max_connections, 5);
+        assert_eq!(config.paging.limits("dummy"), Limits::default());
+        assert!(config.cors.layer().is_none());
+        assert_eq!(config.log.level(), tracing::Level::INFO);
+        assert_eq!(config.health.timeout(), Duration::from_secs(1));
+        assert!(config.features.bulk && !config.features.migrate);
+    }
+
+    #[test]
+    fn config_file_and_env() {
+        let source = r#"
+            [server]
+            bind = "127.0.0.1:8080"
+
+            [database]
+            url = "sqlite::memory:"
+            max_connections = 10
+
+            [paging]
+            max_limit = 500
+
+            [paging.resources.sub_dummy]
+            default_limit = 20
+
+            [cors]
+            allowed_origins = ["https://example.com"]
+
+            [features]
+            bulk = false
+        "#;
+
+        let config = Config::parse(
+            source,
+            vars(&[
+                ("DATABASE_URL", "postgres://localhost/crud"),
+                ("AXUM_CRUD_DATABASE__MAX_CONNECTIONS", "20"),
+                ("AXUM_CRUD_SERVER__BIND", "127.0.0.1:9090"),
+                ("AXUM_CRUD_SERVER__DRAIN_TIMEOUT_SECS", "5"),
+                ("AXUM_CRUD_SERVER__PRE_STOP_DELAY_SECS", "10"),
+                ("AXUM_CRUD_LOG__LEVEL", "debug"),
+                ("AXUM_CRUD_FEATURES__MIGRATE", "true"),
+                ("AXUM_CRUD_PAGING__RESOURCES__DUMMY__MAX_LIMIT", "100"),
+                ("AXUM_CRUD_CONFIG", "ignored.toml"),
+                ("HOME", "/root"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(config.server.bind, "127.0.0.1:9090".parse().unwrap());
+        assert_eq!(config.server.drain_timeout(), Duration::from_secs(5));
+        assert_eq!(config.server.pre_stop_delay(), Duration::from_secs(10));
+        assert_eq!(
+            config.database.url.as_deref(),
+            Some("postgres://localhost/crud")
+        );
+        assert_eq!(config.database.max_connections, 20);
+        assert_eq!(config.log.level(), tracing::Level::DEBUG);
+        assert!(!config.features.bulk && config.features.migrate);
+        assert!(config.cors.layer().is_some());
+
+        assert_eq!(
+            config.paging.limits("sub_dummy"),
+            Limits {
+                default: 20,
+                max: 500
+            }
+        );
+        assert_eq!(
+            config.paging.limits("dummy"),
+            Limits {
+                default: 50,
+                max: 100
+            }
+        );
+    }
+
+    #[test]
+    fn config_invalid() {
+        let source = r#"
+            [database]
+            max_connections = 0
+
+            [paging.resources.dummy]
+            max_limit = 10
+            default_limit = 20
+
+            [cors]
+            allowed_origins = ["*", "https://example.com"]
+            allowed_methods = ["GET", "FETCH ALL"]
+
+            [health]
+            timeout_ms = 0
+
+            [log]
+            level = "loud"
+        "#;
+
+        let Err(ConfigError::Invalid(problems)) = Config::parse(source, vec![]) else {
+            panic!("expected validation problems");
+        };
+
+        assert_eq!(
+            problems,
+            [
+                "database.max_connections must be at least 1",
+                "paging.resources.dummy.max_limit is below the default limit",
+                "cors.allowed_origins cannot mix \"*\" with other origins",
+                "cors.allowed_methods has an invalid method `FETCH ALL`",
+                "health.timeout_ms must be at least 1",
+                "log.level `loud` is not one of error, warn, info, debug or trace",
+            ]
+        );
+    }
+
+    #[test]
+    fn config_parse_errors() {
+        assert!(matches!(
+            Config::parse("[server]\nport = 3000", vec![]),
+            Err(ConfigError::Parse(e)) if e.contains("unknown field `port`")
+        ));
+        assert!(matches!(
+            Config::parse("", vars(&[("AXUM_CRUD_DATABASE__MAX_CONNECTIONS", "many")])),
+            Err(ConfigError::Invalid(problems))
+                if problems == ["AXUM_CRUD_DATABASE__MAX_CONNECTIONS must be an integer, found `many`"]
+        ));
+        assert!(matches!(
+            Config::parse("", vars(&[("AXUM_CRUD_FEATURES__BULK", "1")])),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn config_env_unknown() {
+        let config = Config::parse(
+            "[log]\nlevel = \"info\"",
+            vars(&[
+                ("AXUM_CRUD_LOG__LEVEL__NAME", "info"),
+                ("AXUM_CRUD_SERVER__PORT", "3000"),
+                ("AXUM_CRUD_VERSION", "1.2"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.ignored_env,
+            [
+                "AXUM_CRUD_LOG__LEVEL__NAME",
+                "AXUM_CRUD_SERVER__PORT",
+                "AXUM_CRUD_VERSION"
+            ]
+        );
+    }
+
+    #[test]
+    fn config_env_types() {
+        let config = Config::parse(
+            "",
+            vars(&[
+                ("AXUM_CRUD_LOG__LEVEL", "warn"),
+                ("AXUM_CRUD_DATABASE__URL", "sqlite:1"),
+                (
+                    "AXUM_CRUD_CORS__ALLOWED_ORIGINS",
+                    "https://a.com, https://b.com",
+                ),
+                ("AXUM_CRUD_CORS__ALLOWED_METHODS", "GET"),
+            ]),
+        )
+        .unwrap();
+
+        // Text settings stay text even when they would read as another TOML type.
+        assert_eq!(config.database.url.as_deref(), Some("sqlite:1"));
+        assert_eq!(
+            config.cors.allowed_origins,
+            ["https://a.com", "https://b.com"]
+        );
+        assert_eq!(config.cors.allowed_methods, ["GET"]);
+
+        // Every setting of the file can be overridden, as the type it has there.
+        let source = "[database]\nurl = \"sqlite::memory:\"\n[paging.resources.dummy]\ndefault_limit = 1\nmax_limit = 2";
+        let defaults = toml::Value::try_from(Config::parse(source, vec![]).unwrap()).unwrap();
+        let mut settings = vec![(vec![], defaults)];
+
+        while let Some((path, value)) = settings.pop() {
+            let setting = match value {
+                toml::Value::Table(table) => {
+                    settings.extend(
+                        table
+                            .into_iter()
+                            .map(|(key, value)| ([path.clone(), vec![key]].concat(), value)),
+                    );
+                    continue;
+                }
+                toml::Value::String(_) => Setting::Text,
+                toml::Value::Integer(_) => Setting::Integer,
+                toml::Value::Boolean(_) => Setting::Boolean,
+                toml::Value::Array(_) => Setting::List,
+                _ => unreachable!(),
+            };
+
+            assert_eq!(Setting::at(&path), Some(setting), "{}", path.join("."));
+        }
+    }
+
+    #[test]
+    fn config_check_resources() {
+        let config = Config::parse("[paging.resources.nope]\nmax_limit = 100", vec![]).unwrap();
+
+        assert!(config.check_resources(&[]).is_err());
+        assert!(Config::default().check_resources(&[]).is_ok());
+    }
+}
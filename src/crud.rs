@@ -1,83 +1,444 @@
 use axum::{
-    extract::{Path, State},
-    http::{StatusCode, Uri},
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode, Uri},
     response::{IntoResponse, Response},
     Json,
 };
 
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::Transaction;
 use validator::Validate;
 
-use crate::{prelude::*, router::Pool};
+use crate::{
+    error::Problem,
+    etag, include,
+    prelude::*,
+    router::{Db, Pool},
+};
+
+pub const APPLICATION_MERGE_PATCH_JSON: &str = "application/merge-patch+json";
+pub const APPLICATION_JSON_PATCH_JSON: &str = "application/json-patch+json";
 
 pub async fn create<T>(uri: Uri, State(pool): State<Pool>, Json(mut new): Json<T>) -> Response
 where
-    T: Database<Pool> + Validate + Check,
+    T: Database<Db> + Validate + Check,
+{
+    let tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return CrudError::from(e).into_response(),
+    };
+
+    store(tx, &uri, &mut new).await
+}
+
+/// Checks and inserts `new`, committing `tx` on success. Every early return drops the
+/// transaction, which rolls it back.
+async fn store<T>(mut tx: Transaction<'_, Db>, uri: &Uri, new: &mut T) -> Response
+where
+    T: Database<Db> + Validate + Check,
+{
+    if let Err(errors) = new.validate() {
+        return CrudError::from(errors).into_response();
+    }
+
+    if let Err(messages) = new.check_create() {
+        return CrudError::from(messages).into_response();
+    }
+
+    let id = match T::insert(new, &mut tx).await {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = tx.commit().await {
+        return CrudError::from(e).into_response();
+    }
+
+    (
+        StatusCode::CREATED,
+        [
+            ("Location", format!("{}{}", uri.path(), id)),
+            ("X-Item-ID", format!("{}", id)),
+        ],
+    )
+        .into_response()
+}
+
+#[derive(Deserialize)]
+pub struct RetrieveParams {
+    /// Comma separated columns to return instead of all of them.
+    fields: Option<String>,
+    /// Comma separated related resources to embed.
+    include: Option<String>,
+}
+
+pub async fn retrieve<T>(
+    State(pool): State<Pool>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    Query(query): Query<RetrieveParams>,
+) -> Response
+where
+    T: Database<Db> + DatabaseFetchAll<Db> + Include<Db> + Serialize,
 {
-    if new.validate().is_err() || new.check_create().is_err() {
-        return StatusCode::UNPROCESSABLE_ENTITY.into_response();
+    let fields = match query.fields.as_deref().map(T::select).transpose() {
+        Ok(fields) => fields,
+        Err(e) => return e.into_response(),
+    };
+
+    let includes = match query.include.as_deref().map(T::includes).transpose() {
+        Ok(includes) => includes.unwrap_or_default(),
+        Err(e) => return e.into_response(),
+    };
+
+    if !includes.is_empty() {
+        return match embedded::<T>(&pool, id, fields.as_deref(), &includes).await {
+            Ok(old) => representation(&headers, old),
+            Err(e) => e.into_response(),
+        };
     }
 
-    match T::insert(&new, &pool).await {
-        Ok(id) => (
-            StatusCode::CREATED,
-            [
-                ("Location", format!("{}{}", uri.path(), id)),
-                ("X-Item-ID", format!("{}", id)),
-            ],
-        )
-            .into_response(),
-        Err(_) => StatusCode::NOT_ACCEPTABLE.into_response(),
+    match fields {
+        Some(fields) => match T::fetch_one_fields(&pool, id, &fields).await {
+            Ok(old) => representation(&headers, old),
+            Err(e) => e.into_response(),
+        },
+        None => match T::fetch_one(&pool, id).await {
+            Ok(old) => representation(&headers, old),
+            Err(e) => e.into_response(),
+        },
     }
 }
 
-pub async fn retrieve<T>(State(pool): State<Pool>, Path(id): Path<i64>) -> Response
+/// The row `id`, or its `fields` when set, with `includes` embedded.
+async fn embedded<T>(
+    pool: &Pool,
+    id: i64,
+    fields: Option<&[SelectField]>,
+    includes: &[IncludeField],
+) -> Result<Map<String, Value>, CrudError>
 where
-    T: Database<Pool> + Serialize,
+    T: Database<Db> + DatabaseFetchAll<Db> + Include<Db> + Serialize,
 {
-    match T::fetch_one(&pool, id).await {
-        Ok(old) => (StatusCode::OK, Json(old)).into_response(),
-        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    let mut rows = match fields {
+        Some(fields) => {
+            let projection = include::projection(fields, includes);
+            vec![T::fetch_one_fields(pool, id, &projection).await?]
+        }
+        None => include::objects(&[T::fetch_one(pool, id).await?])?,
+    };
+
+    include::embed::<T>(pool, includes, &mut rows).await?;
+
+    if let Some(fields) = fields {
+        include::retain(&mut rows, fields, includes);
+    }
+
+    Ok(rows.pop().unwrap_or_default())
+}
+
+/// `old` with its ETag, or 304 when `If-None-Match` already has it.
+fn representation<T: Serialize>(headers: &HeaderMap, old: T) -> Response {
+    let Some(etag) = etag::etag(&old) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    if !etag::if_none_match(headers, Some(&etag)) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
     }
+
+    (StatusCode::OK, [(header::ETAG, etag)], Json(old)).into_response()
 }
 
 pub async fn update<T>(
     State(pool): State<Pool>,
     Path(id): Path<i64>,
+    headers: HeaderMap,
     Json(mut new): Json<T>,
-) -> StatusCode
+) -> Response
+where
+    T: Database<Db> + Validate + Check + Serialize,
+{
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return CrudError::from(e).into_response(),
+    };
+
+    let old = match T::fetch_one(&mut tx, id).await {
+        Ok(old) => old,
+        Err(e) => return e.into_response(),
+    };
+
+    if new.get_id() != id {
+        return id_changed::<T>();
+    }
+
+    save(tx, &headers, old, &mut new).await
+}
+
+/// The row id comes from the path, a body naming another row is refused rather than written.
+fn id_changed<T: Database<Db>>() -> Response {
+    Problem::new(StatusCode::BAD_REQUEST)
+        .detail(format!("{} cannot be changed", T::FIELD_ID))
+        .into_response()
+}
+
+fn precondition_failed() -> Response {
+    Problem::new(StatusCode::PRECONDITION_FAILED)
+        .detail("If-Match does not match the current representation")
+        .into_response()
+}
+
+fn parent_mismatch() -> Response {
+    Problem::new(StatusCode::BAD_REQUEST)
+        .detail("The parent id does not match the path")
+        .into_response()
+}
+
+/// Checks and writes `new` over `old`, committing `tx` on success. Every early return drops
+/// the transaction, which rolls it back.
+async fn save<T>(mut tx: Transaction<'_, Db>, headers: &HeaderMap, old: T, new: &mut T) -> Response
+where
+    T: Database<Db> + Validate + Check + Serialize,
+{
+    if !etag::if_match(headers, etag::etag(&old).as_deref()) {
+        return precondition_failed();
+    }
+
+    if let Err(errors) = new.validate() {
+        return CrudError::from(errors).into_response();
+    }
+
+    if let Err(messages) = new.check_update(old) {
+        return CrudError::from(messages).into_response();
+    }
+
+    if let Err(e) = T::update(new, &mut tx).await {
+        return e.into_response();
+    }
+
+    // The stored row, not the request body, is what the next If-Match gets compared to.
+    let stored = match T::fetch_one(&mut tx, new.get_id()).await {
+        Ok(stored) => stored,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = tx.commit().await {
+        return CrudError::from(e).into_response();
+    }
+
+    match etag::etag(&stored) {
+        Some(etag) => (StatusCode::OK, [(header::ETAG, etag)]).into_response(),
+        None => StatusCode::OK.into_response(),
+    }
+}
+
+/// Loads the row `id` and applies a JSON Merge Patch (RFC 7396) or JSON Patch (RFC 6902)
+/// document to it, depending on the request content type. Returns the old and the patched row.
+async fn apply_patch<T>(
+    tx: &mut Transaction<'_, Db>,
+    id: i64,
+    headers: &HeaderMap,
+    body: &Bytes,
+) -> Result<(T, T), Response>
+where
+    T: Database<Db> + Serialize + DeserializeOwned,
+{
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_ascii_lowercase());
+
+    let merge = match content_type.as_deref() {
+        Some(APPLICATION_MERGE_PATCH_JSON) => true,
+        Some(APPLICATION_JSON_PATCH_JSON) => false,
+        _ => {
+            return Err(Problem::new(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+                .detail(format!(
+                    "Use {APPLICATION_MERGE_PATCH_JSON} or {APPLICATION_JSON_PATCH_JSON}"
+                ))
+                .into_response())
+        }
+    };
+
+    let old = T::fetch_one(tx, id).await.map_err(|e| e.into_response())?;
+
+    let Ok(mut doc) = serde_json::to_value(&old) else {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+    };
+
+    if merge {
+        let Ok(patch) = serde_json::from_slice::<Value>(body) else {
+            return Err(Problem::new(StatusCode::BAD_REQUEST)
+                .detail("The merge patch document is not valid JSON")
+                .into_response());
+        };
+        json_patch::merge(&mut doc, &patch);
+    } else {
+        let Ok(patch) = serde_json::from_slice::<json_patch::Patch>(body) else {
+            return Err(Problem::new(StatusCode::BAD_REQUEST)
+                .detail("The JSON patch document is malformed")
+                .into_response());
+        };
+        if let Err(e) = json_patch::patch(&mut doc, &patch) {
+            return Err(Problem::new(StatusCode::CONFLICT)
+                .detail(e.to_string())
+                .into_response());
+        }
+    }
+
+    if doc.get(T::FIELD_ID) != Some(&Value::from(id)) {
+        return Err(id_changed::<T>());
+    }
+
+    match serde_json::from_value(doc) {
+        Ok(new) => Ok((old, new)),
+        Err(e) => Err(Problem::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .detail(e.to_string())
+            .into_response()),
+    }
+}
+
+pub async fn patch<T>(
+    State(pool): State<Pool>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response
+where
+    T: Database<Db> + Validate + Check + Serialize + DeserializeOwned,
+{
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return CrudError::from(e).into_response(),
+    };
+
+    let (old, mut new) = match apply_patch::<T>(&mut tx, id, &headers, &body).await {
+        Ok(rows) => rows,
+        Err(response) => return response,
+    };
+
+    save(tx, &headers, old, &mut new).await
+}
+
+pub async fn delete<T>(
+    State(pool): State<Pool>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Response
+where
+    T: Database<Db> + Check + Serialize,
+{
+    let tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return CrudError::from(e).into_response(),
+    };
+
+    remove::<T>(tx, id, &headers).await
+}
+
+/// Checks and deletes the row `id` along with its children, committing `tx` on success.
+async fn remove<T>(mut tx: Transaction<'_, Db>, id: i64, headers: &HeaderMap) -> Response
+where
+    T: Database<Db> + Check + Serialize,
+{
+    let old = match T::fetch_one(&mut tx, id).await {
+        Ok(old) => old,
+        Err(e) => return e.into_response(),
+    };
+
+    if !etag::if_match(headers, etag::etag(&old).as_deref()) {
+        return precondition_failed();
+    }
+
+    if let Err(messages) = old.check_delete() {
+        return CrudError::from(messages).into_response();
+    }
+
+    if let Err(e) = T::delete_children(&mut tx, id, !T::FIELD_DELETED_AT.is_empty()).await {
+        return e.into_response();
+    }
+
+    if let Err(e) = T::delete(&mut tx, id).await {
+        return e.into_response();
+    }
+
+    match tx.commit().await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => CrudError::from(e).into_response(),
+    }
+}
+
+pub async fn restore<T>(
+    State(pool): State<Pool>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Response
 where
-    T: Database<Pool> + Validate + Check,
+    T: Database<Db> + Serialize,
 {
-    let Ok(old) = T::fetch_one(&pool, id).await else {
-        return StatusCode::NOT_FOUND;
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return CrudError::from(e).into_response(),
     };
 
-    if new.validate().is_err() || new.check_update(old).is_err() {
-        return StatusCode::UNPROCESSABLE_ENTITY;
+    let old = match T::fetch_trashed(&mut tx, id).await {
+        Ok(old) => old,
+        Err(e) => return e.into_response(),
+    };
+
+    if !etag::if_match(&headers, etag::etag(&old).as_deref()) {
+        return precondition_failed();
+    }
+
+    if let Err(e) = T::restore(&mut tx, id).await {
+        return e.into_response();
     }
 
-    match T::update(&new, &pool).await {
-        Ok(_) => StatusCode::OK,
-        Err(_) => StatusCode::NOT_ACCEPTABLE,
+    match tx.commit().await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => CrudError::from(e).into_response(),
     }
 }
 
-pub async fn delete<T>(State(pool): State<Pool>, Path(id): Path<i64>) -> StatusCode
+/// Permanently removes a row that was already deleted, live rows have to be deleted first.
+/// The delete policies of its children, left alone by the soft delete, apply now.
+pub async fn purge<T>(State(pool): State<Pool>, Path(id): Path<i64>, headers: HeaderMap) -> Response
 where
-    T: Database<Pool> + Check,
+    T: Database<Db> + Check + Serialize,
 {
-    let Ok(old) = T::fetch_one(&pool, id).await else {
-        return StatusCode::NOT_FOUND;
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return CrudError::from(e).into_response(),
     };
 
-    if old.check_delete().is_err() {
-        return StatusCode::UNPROCESSABLE_ENTITY;
+    let old = match T::fetch_trashed(&mut tx, id).await {
+        Ok(old) => old,
+        Err(e) => return e.into_response(),
+    };
+
+    if !etag::if_match(&headers, etag::etag(&old).as_deref()) {
+        return precondition_failed();
+    }
+
+    if let Err(messages) = old.check_delete() {
+        return CrudError::from(messages).into_response();
     }
 
-    match T::delete(&pool, id).await {
-        Ok(_) => StatusCode::NO_CONTENT,
-        Err(_) => StatusCode::NOT_ACCEPTABLE,
+    if let Err(e) = T::delete_children(&mut tx, id, false).await {
+        return e.into_response();
+    }
+
+    if let Err(e) = T::purge(&mut tx, id).await {
+        return e.into_response();
+    }
+
+    match tx.commit().await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => CrudError::from(e).into_response(),
     }
 }
 
@@ -88,83 +449,140 @@ pub async fn sub_create<T>(
     Json(mut new): Json<T>,
 ) -> Response
 where
-    T: Database<Pool> + MatchParent<Pool> + Validate + Check,
-    T::Parent: Database<Pool>,
+    T: Database<Db> + MatchParent<Db> + Validate + Check,
+    T::Parent: Database<Db>,
 {
-    if T::Parent::fetch_one(&pool, parent_id).await.is_err() {
-        return StatusCode::NOT_FOUND.into_response();
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return CrudError::from(e).into_response(),
+    };
+
+    if let Err(e) = T::Parent::fetch_one(&mut tx, parent_id).await {
+        return e.into_response();
     }
 
     if new.get_parent_id() != parent_id {
-        return StatusCode::BAD_REQUEST.into_response();
+        return parent_mismatch();
     }
 
-    create(uri, State(pool), Json(new)).await
+    store(tx, &uri, &mut new).await
 }
 
 pub async fn sub_retrieve<T>(
     State(pool): State<Pool>,
     Path((parent_id, id)): Path<(i64, i64)>,
+    headers: HeaderMap,
+    query: Query<RetrieveParams>,
 ) -> Response
 where
-    T: Database<Pool> + MatchParent<Pool> + Serialize,
+    T: Database<Db> + DatabaseFetchAll<Db> + Include<Db> + MatchParent<Db> + Serialize,
 {
-    if T::fetch_parent(&pool, parent_id, id).await.is_err() {
-        return StatusCode::NOT_FOUND.into_response();
+    if let Err(e) = T::fetch_parent(&pool, parent_id, id).await {
+        return e.into_response();
     }
 
-    retrieve::<T>(State(pool), Path(id)).await
+    retrieve::<T>(State(pool), Path(id), headers, query).await
 }
 
 pub async fn sub_update<T>(
     State(pool): State<Pool>,
     Path((parent_id, id)): Path<(i64, i64)>,
+    headers: HeaderMap,
     Json(mut new): Json<T>,
-) -> StatusCode
+) -> Response
+where
+    T: Database<Db> + MatchParent<Db> + Validate + Check + Serialize,
+{
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return CrudError::from(e).into_response(),
+    };
+
+    if let Err(e) = T::fetch_parent(&mut tx, parent_id, id).await {
+        return e.into_response();
+    }
+
+    if new.get_parent_id() != parent_id {
+        return parent_mismatch();
+    }
+
+    let old = match T::fetch_one(&mut tx, id).await {
+        Ok(old) => old,
+        Err(e) => return e.into_response(),
+    };
+
+    if new.get_id() != id {
+        return id_changed::<T>();
+    }
+
+    save(tx, &headers, old, &mut new).await
+}
+
+pub async fn sub_patch<T>(
+    State(pool): State<Pool>,
+    Path((parent_id, id)): Path<(i64, i64)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response
 where
-    T: Database<Pool> + MatchParent<Pool> + Validate + Check,
+    T: Database<Db> + MatchParent<Db> + Validate + Check + Serialize + DeserializeOwned,
 {
-    if T::fetch_parent(&pool, parent_id, id).await.is_err() {
-        return StatusCode::NOT_FOUND;
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return CrudError::from(e).into_response(),
+    };
+
+    if let Err(e) = T::fetch_parent(&mut tx, parent_id, id).await {
+        return e.into_response();
     }
 
+    let (old, mut new) = match apply_patch::<T>(&mut tx, id, &headers, &body).await {
+        Ok(rows) => rows,
+        Err(response) => return response,
+    };
+
     if new.get_parent_id() != parent_id {
-        return StatusCode::BAD_REQUEST;
+        return parent_mismatch();
     }
 
-    update(State(pool), Path(id), Json(new)).await
+    save(tx, &headers, old, &mut new).await
 }
 
 pub async fn sub_delete<T>(
     State(pool): State<Pool>,
     Path((parent_id, id)): Path<(i64, i64)>,
-) -> StatusCode
+    headers: HeaderMap,
+) -> Response
 where
-    T: Database<Pool> + MatchParent<Pool> + Check,
+    T: Database<Db> + MatchParent<Db> + Check + Serialize,
 {
-    if T::fetch_parent(&pool, parent_id, id).await.is_err() {
-        return StatusCode::NOT_FOUND;
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return CrudError::from(e).into_response(),
+    };
+
+    if let Err(e) = T::fetch_parent(&mut tx, parent_id, id).await {
+        return e.into_response();
     }
 
-    delete::<T>(State(pool), Path(id)).await
+    remove::<T>(tx, id, &headers).await
 }
 
 #[cfg(test)]
 mod tests {
-    use axum::{
-        http::{self, Request, StatusCode},
-        routing::{delete, get, post, put},
-        Router,
-    };
+    use axum::http::{self, Request, StatusCode};
 
     use crate::{
-        crud,
+        crud, etag,
         prelude::*,
+        router::CrudRouter,
         types::{dummy::Dummy, sub_dummy::SubDummy},
     };
+    use axum::{routing::delete, Router};
     use http_body_util::BodyExt;
+    use serde::{Deserialize, Serialize};
     use serde_json::json;
-    use sqlx::{any::AnyPoolOptions, Any, Executor, Pool};
+    use sqlx::{any::AnyPoolOptions, Any, Executor, FromRow, Pool};
     use tower::ServiceExt;
 
     async fn database(size: i64) -> Pool<Any> {
@@ -175,17 +593,7 @@ mod tests {
             .await
             .unwrap();
 
-        let _ = pool
-            .execute(sqlx::raw_sql(
-                "CREATE TABLE dummy (id_dummy bigint, name text);",
-            ))
-            .await;
-
-        let _ = pool
-            .execute(sqlx::raw_sql(
-                "CREATE TABLE sub_dummy (id_sub_dummy bigint, name text, id_dummy bigint);",
-            ))
-            .await;
+        crate::migrate::up(&pool).await.unwrap();
 
         for i in 1..=size {
             let _ = Dummy::insert(
@@ -213,21 +621,10 @@ mod tests {
     }
 
     async fn router(pool: Pool<Any>) -> axum::Router {
-        Router::new()
-            .route("/dummy/", post(crud::create::<Dummy>))
-            .route("/dummy/:id", get(crud::retrieve::<Dummy>))
-            .route("/dummy/:id", put(crud::update::<Dummy>))
-            .route("/dummy/:id", delete(crud::delete::<Dummy>))
-            .route("/dummy/:id/subdummy/", post(crud::sub_create::<SubDummy>))
-            .route(
-                "/dummy/:id/subdummy/:id",
-                get(crud::sub_retrieve::<SubDummy>),
-            )
-            .route("/dummy/:id/subdummy/:id", put(crud::sub_update::<SubDummy>))
-            .route(
-                "/dummy/:id/subdummy/:id",
-                delete(crud::sub_delete::<SubDummy>),
-            )
+        CrudRouter::new()
+            .resource::<Dummy>("/dummy")
+            .nested::<SubDummy>("/dummy/:id/subdummy")
+            .into_router()
             .with_state(pool)
     }
 
@@ -319,12 +716,12 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn create_empty() {
-        let pool = database(0).await;
+    async fn create_duplicate() {
+        let pool = database(1).await;
 
         let app = router(pool.clone()).await;
 
-        let body = "".to_string();
+        let body = json!({"id_dummy": 1, "name": "name"}).to_string();
 
         let response = app
             .oneshot(
@@ -338,16 +735,19 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let dummy = Dummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(dummy.name, "name-1");
     }
 
     #[tokio::test]
-    async fn create_invalid() {
+    async fn create_empty() {
         let pool = database(0).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name", "is_valid": false}).to_string();
+        let body = "".to_string();
 
         let response = app
             .oneshot(
@@ -361,16 +761,16 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
-    async fn create_bad_json() {
+    async fn create_invalid() {
         let pool = database(0).await;
 
         let app = router(pool.clone()).await;
 
-        let body = r#"{"id_dummy": 1, "name": "name" 123, "is_valid": false}:"#.to_string();
+        let body = json!({"id_dummy": 1, "name": "name", "is_valid": false}).to_string();
 
         let response = app
             .oneshot(
@@ -384,67 +784,87 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
     }
 
     #[tokio::test]
-    async fn create_no_content_type() {
+    async fn create_invalid_problem() {
         let pool = database(0).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name"}).to_string();
+        let body = json!({"id_dummy": 1, "name": "name", "is_valid": false}).to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
                     .method(http::Method::POST)
                     .uri("/dummy/")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            response
+                .headers()
+                .get(http::header::CONTENT_TYPE)
+                .map(|v| v.to_str().unwrap()),
+            Some("application/problem+json")
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(problem["status"], 422);
+        assert_eq!(problem["checks"][0], "is_valid must not be false");
     }
 
     #[tokio::test]
-    async fn create_wrong_content_type() {
+    async fn create_validation_problem() {
         let pool = database(0).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name"}).to_string();
+        let body = json!({"id_dummy": 1, "name": ""}).to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
                     .method(http::Method::POST)
                     .uri("/dummy/")
-                    .header(http::header::CONTENT_TYPE, mime::CSV.as_ref())
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(problem["errors"]["name"][0], "length");
+        assert!(Dummy::fetch_one(&pool, 1).await.is_err());
     }
 
     #[tokio::test]
-    async fn create_sub_ok() {
-        let pool = database(1).await;
+    async fn create_bad_json() {
+        let pool = database(0).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name", "id_sub_dummy": 2}).to_string();
+        let body = r#"{"id_dummy": 1, "name": "name" 123, "is_valid": false}:"#.to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
                     .method(http::Method::POST)
-                    .uri("/dummy/1/subdummy/")
+                    .uri("/dummy/")
                     .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
                     .body(body)
                     .unwrap(),
@@ -452,52 +872,120 @@ mod tests {
             .await
             .unwrap();
 
-        let dummy = SubDummy::fetch_one(&pool, 2).await.unwrap();
-
-        assert_eq!(response.status(), StatusCode::CREATED);
-        assert_eq!(dummy.id_dummy, 1);
-        assert_eq!(dummy.name, "name");
-        assert_eq!(dummy.id_sub_dummy, 2);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
-    async fn create_sub_not_found() {
+    async fn create_no_content_type() {
         let pool = database(0).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name", "id_sub_dummy": 2}).to_string();
+        let body = json!({"id_dummy": 1, "name": "name"}).to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
                     .method(http::Method::POST)
-                    .uri("/dummy/1/subdummy/")
-                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .uri("/dummy/")
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
-        assert!(SubDummy::fetch_one(&pool, 2).await.is_err());
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
     }
 
     #[tokio::test]
-    async fn create_sub_mismatch() {
-        let pool = database(1).await;
+    async fn create_wrong_content_type() {
+        let pool = database(0).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 2, "name": "name", "id_sub_dummy": 2}).to_string();
+        let body = json!({"id_dummy": 1, "name": "name"}).to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
                     .method(http::Method::POST)
-                    .uri("/dummy/1/subdummy/")
-                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .uri("/dummy/")
+                    .header(http::header::CONTENT_TYPE, mime::CSV.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn create_sub_ok() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 1, "name": "name", "id_sub_dummy": 2}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/dummy/1/subdummy/")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let dummy = SubDummy::fetch_one(&pool, 2).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(dummy.id_dummy, 1);
+        assert_eq!(dummy.name, "name");
+        assert_eq!(dummy.id_sub_dummy, 2);
+    }
+
+    #[tokio::test]
+    async fn create_sub_not_found() {
+        let pool = database(0).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 1, "name": "name", "id_sub_dummy": 2}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/dummy/1/subdummy/")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(SubDummy::fetch_one(&pool, 2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn create_sub_mismatch() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 2, "name": "name", "id_sub_dummy": 2}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/dummy/1/subdummy/")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
                     .body(body)
                     .unwrap(),
             )
@@ -536,66 +1024,903 @@ mod tests {
         assert_eq!(dummy.name, "name-1");
     }
 
+    #[tokio::test]
+    async fn retrieve_fields() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/1?fields=name")
+                    .body("".to_string())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let dummy: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(dummy, serde_json::json!({"name": "name-1"}));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/1?fields=name,is_valid")
+                    .body("".to_string())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn retrieve_include() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/2/subdummy/2?fields=name&include=parent")
+                    .body("".to_string())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let sub_dummy: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            sub_dummy,
+            json!({"name": "name-2", "parent": {"id_dummy": 2, "name": "name-2", "is_valid": null}})
+        );
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/1?include=sub_dummy")
+                    .body("".to_string())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let dummy: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(dummy["name"], "name-1");
+        assert_eq!(
+            dummy["sub_dummy"],
+            json!([{"id_sub_dummy": 1, "id_dummy": 1, "name": "name-1", "is_valid": null}])
+        );
+    }
+
+    #[tokio::test]
+    async fn retrieve_etag() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let etag = etag::etag(&Dummy::fetch_one(&pool, 1).await.unwrap()).unwrap();
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(http::header::ETAG)
+                .map(|v| v.to_str().unwrap().to_string()),
+            Some(etag)
+        );
+    }
+
+    #[tokio::test]
+    async fn retrieve_not_modified() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let etag = etag::etag(&Dummy::fetch_one(&pool, 1).await.unwrap()).unwrap();
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(http::header::IF_NONE_MATCH, etag.clone())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            response
+                .headers()
+                .get(http::header::ETAG)
+                .map(|v| v.to_str().unwrap().to_string()),
+            Some(etag)
+        );
+    }
+
+    #[tokio::test]
+    async fn retrieve_modified() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(http::header::IF_NONE_MATCH, "\"0\"")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn retrieve_sub_not_modified() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let etag = etag::etag(&SubDummy::fetch_one(&pool, 1).await.unwrap()).unwrap();
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/1/subdummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(http::header::IF_NONE_MATCH, etag)
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    }
+
     #[tokio::test]
     async fn retrieve_inexsistent() {
         let pool = database(0).await;
 
         let app = router(pool.clone()).await;
 
-        let body = "".to_string();
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn retrieve_bad_id() {
+        let pool = database(0).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/a")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn retrieve_sub_ok() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/1/subdummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let dummy: Dummy = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(dummy.id_dummy, 1);
+        assert_eq!(dummy.name, "name-1");
+    }
+
+    #[tokio::test]
+    async fn retrieve_sub_not_found() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/2/subdummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn retrieve_sub_mismatch() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/2/subdummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn update_ok() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let dummy = Dummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(dummy.id_dummy, 1);
+        assert_eq!(dummy.name, "name-new");
+    }
+
+    #[tokio::test]
+    async fn update_no_content_type() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/1")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn update_wrong_content_type() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::CSV.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn update_empty() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn update_bad_id() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/a")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn update_id_change() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 2, "name": "name-new"}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(Dummy::fetch_one(&pool, 1).await.unwrap().name, "name-1");
+        assert_eq!(Dummy::fetch_one(&pool, 2).await.unwrap().name, "name-2");
+    }
+
+    #[tokio::test]
+    async fn update_bad_json() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = r#"{"id_dummy": 1, "name": "name" 123, "is_valid": false}:"#.to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn update_inexistent() {
+        let pool = database(0).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn update_invalid() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 1, "name": "name", "is_valid": false}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn update_sub_ok() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 1, "name": "name-new", "id_sub_dummy": 1}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/1/subdummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let dummy = SubDummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(dummy.id_sub_dummy, 1);
+        assert_eq!(dummy.id_dummy, 1);
+        assert_eq!(dummy.name, "name-new");
+    }
+
+    #[tokio::test]
+    async fn update_sub_not_found() {
+        let pool = database(1).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 1, "name": "name-new", "id_sub_dummy": 1}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/2/subdummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let dummy = SubDummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(dummy.id_sub_dummy, 1);
+        assert_eq!(dummy.id_dummy, 1);
+        assert_eq!(dummy.name, "name-1");
+    }
+
+    #[tokio::test]
+    async fn update_sub_mismatch_data() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 2, "name": "name-new", "id_sub_dummy": 1}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/1/subdummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response
+                .headers()
+                .get(http::header::CONTENT_TYPE)
+                .map(|v| v.to_str().unwrap()),
+            Some("application/problem+json")
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(problem["status"], 400);
+        assert_eq!(problem["detail"], "The parent id does not match the path");
+    }
+
+    #[tokio::test]
+    async fn update_sub_mismatch() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 1, "name": "name-new", "id_sub_dummy": 1}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PUT)
+                    .uri("/dummy/2/subdummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let dummy = SubDummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(dummy.id_sub_dummy, 1);
+        assert_eq!(dummy.id_dummy, 1);
+        assert_eq!(dummy.name, "name-1");
+    }
+
+    #[tokio::test]
+    async fn patch_merge_ok() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"name": "name-new"}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PATCH)
+                    .uri("/dummy/1")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        crud::APPLICATION_MERGE_PATCH_JSON,
+                    )
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let dummy = Dummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(dummy.id_dummy, 1);
+        assert_eq!(dummy.name, "name-new");
+    }
+
+    #[tokio::test]
+    async fn patch_json_patch_ok() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!([{"op": "replace", "path": "/name", "value": "name-new"}]).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PATCH)
+                    .uri("/dummy/1")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        crud::APPLICATION_JSON_PATCH_JSON,
+                    )
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let dummy = Dummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(dummy.name, "name-new");
+    }
+
+    #[tokio::test]
+    async fn patch_json_patch_test_failed() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!([{"op": "test", "path": "/name", "value": "other"}, {"op": "replace", "path": "/name", "value": "name-new"}]).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PATCH)
+                    .uri("/dummy/1")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        crud::APPLICATION_JSON_PATCH_JSON,
+                    )
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let dummy = Dummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(dummy.name, "name-1");
+    }
+
+    #[tokio::test]
+    async fn patch_wrong_content_type() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"name": "name-new"}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PATCH)
+                    .uri("/dummy/1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn patch_inexistent() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"name": "name-new"}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PATCH)
+                    .uri("/dummy/3")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        crud::APPLICATION_MERGE_PATCH_JSON,
+                    )
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn patch_invalid() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"is_valid": false}).to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PATCH)
+                    .uri("/dummy/1")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        crud::APPLICATION_MERGE_PATCH_JSON,
+                    )
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let dummy = Dummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(dummy.name, "name-1");
+    }
+
+    #[tokio::test]
+    async fn patch_id_change() {
+        let pool = database(2).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = json!({"id_dummy": 2}).to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .method(http::Method::GET)
+                    .method(http::Method::PATCH)
                     .uri("/dummy/1")
-                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        crud::APPLICATION_MERGE_PATCH_JSON,
+                    )
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
-    async fn retrieve_bad_id() {
-        let pool = database(0).await;
+    async fn patch_bad_type() {
+        let pool = database(2).await;
 
         let app = router(pool.clone()).await;
 
-        let body = "".to_string();
+        let body = json!({"name": 1}).to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .method(http::Method::GET)
-                    .uri("/dummy/a")
-                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .method(http::Method::PATCH)
+                    .uri("/dummy/1")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        crud::APPLICATION_MERGE_PATCH_JSON,
+                    )
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
     }
 
     #[tokio::test]
-    async fn retrieve_sub_ok() {
-        let pool = database(1).await;
+    async fn patch_sub_ok() {
+        let pool = database(2).await;
 
         let app = router(pool.clone()).await;
 
-        let body = "".to_string();
+        let body = json!({"name": "name-new"}).to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .method(http::Method::GET)
+                    .method(http::Method::PATCH)
                     .uri("/dummy/1/subdummy/1")
-                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        crud::APPLICATION_MERGE_PATCH_JSON,
+                    )
                     .body(body)
                     .unwrap(),
             )
@@ -604,27 +1929,29 @@ mod tests {
 
         assert_eq!(response.status(), StatusCode::OK);
 
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let dummy: Dummy = serde_json::from_slice(&body).unwrap();
+        let dummy = SubDummy::fetch_one(&pool, 1).await.unwrap();
 
         assert_eq!(dummy.id_dummy, 1);
-        assert_eq!(dummy.name, "name-1");
+        assert_eq!(dummy.name, "name-new");
     }
 
     #[tokio::test]
-    async fn retrieve_sub_not_found() {
-        let pool = database(1).await;
+    async fn patch_sub_mismatch() {
+        let pool = database(2).await;
 
         let app = router(pool.clone()).await;
 
-        let body = "".to_string();
+        let body = json!({"name": "name-new"}).to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .method(http::Method::GET)
+                    .method(http::Method::PATCH)
                     .uri("/dummy/2/subdummy/1")
-                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        crud::APPLICATION_MERGE_PATCH_JSON,
+                    )
                     .body(body)
                     .unwrap(),
             )
@@ -632,43 +1959,54 @@ mod tests {
             .unwrap();
 
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let dummy = SubDummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(dummy.name, "name-1");
     }
 
     #[tokio::test]
-    async fn retrieve_sub_mismatch() {
+    async fn patch_sub_mismatch_data() {
         let pool = database(2).await;
 
         let app = router(pool.clone()).await;
 
-        let body = "".to_string();
+        let body = json!({"id_dummy": 2}).to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .method(http::Method::GET)
-                    .uri("/dummy/2/subdummy/1")
-                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .method(http::Method::PATCH)
+                    .uri("/dummy/1/subdummy/1")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        crud::APPLICATION_MERGE_PATCH_JSON,
+                    )
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let dummy = SubDummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(dummy.id_dummy, 1);
     }
 
     #[tokio::test]
-    async fn update_ok() {
+    async fn delete_ok() {
         let pool = database(1).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
+        let body = "".to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .method(http::Method::PUT)
+                    .method(http::Method::DELETE)
                     .uri("/dummy/1")
                     .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
                     .body(body)
@@ -677,111 +2015,332 @@ mod tests {
             .await
             .unwrap();
 
-        let dummy = Dummy::fetch_one(&pool, 1).await.unwrap();
+        let dummy = Dummy::fetch_one(&pool, 1).await;
 
-        assert_eq!(response.status(), StatusCode::OK);
-        assert_eq!(dummy.id_dummy, 1);
-        assert_eq!(dummy.name, "name-new");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(dummy.is_err());
     }
 
     #[tokio::test]
-    async fn update_no_content_type() {
+    async fn delete_restore_purge() {
         let pool = database(1).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
+        let request = |method: http::Method, uri: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body("".to_string())
+                .unwrap()
+        };
+        let rows = || async {
+            sqlx::query_scalar::<_, i64>("SELECT count(*) FROM dummy")
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
 
         let response = app
-            .oneshot(
-                Request::builder()
-                    .method(http::Method::PUT)
-                    .uri("/dummy/1")
-                    .body(body)
-                    .unwrap(),
-            )
+            .clone()
+            .oneshot(request(http::Method::DELETE, "/dummy/1"))
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(Dummy::fetch_one(&pool, 1).await.is_err());
+        assert_eq!(rows().await, 1);
+
+        for (method, uri, status) in [
+            (http::Method::GET, "/dummy/1", StatusCode::NOT_FOUND),
+            (http::Method::DELETE, "/dummy/1", StatusCode::NOT_FOUND),
+            (
+                http::Method::POST,
+                "/dummy/1/restore",
+                StatusCode::NO_CONTENT,
+            ),
+            (
+                http::Method::POST,
+                "/dummy/1/restore",
+                StatusCode::NOT_FOUND,
+            ),
+            (http::Method::GET, "/dummy/1", StatusCode::OK),
+            (
+                http::Method::DELETE,
+                "/dummy/1/purge",
+                StatusCode::NOT_FOUND,
+            ),
+            (http::Method::DELETE, "/dummy/1", StatusCode::NO_CONTENT),
+            (
+                http::Method::DELETE,
+                "/dummy/1/purge",
+                StatusCode::NO_CONTENT,
+            ),
+            (
+                http::Method::POST,
+                "/dummy/1/restore",
+                StatusCode::NOT_FOUND,
+            ),
+        ] {
+            let response = app.clone().oneshot(request(method, uri)).await.unwrap();
+
+            assert_eq!(response.status(), status, "{uri}");
+        }
+
+        assert_eq!(rows().await, 0);
     }
 
     #[tokio::test]
-    async fn update_wrong_content_type() {
+    async fn restore_purge_preconditions() {
         let pool = database(1).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
+        let request = |method: http::Method, uri: &str, etag: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(http::header::IF_MATCH, etag)
+                .body("".to_string())
+                .unwrap()
+        };
+
+        Dummy::delete(&pool, 1).await.unwrap();
+        let etag = etag::etag(&Dummy::fetch_trashed(&pool, 1).await.unwrap()).unwrap();
+
+        for (method, uri, etag, status) in [
+            (
+                http::Method::POST,
+                "/dummy/1/restore",
+                "\"0\"",
+                StatusCode::PRECONDITION_FAILED,
+            ),
+            (
+                http::Method::DELETE,
+                "/dummy/1/purge",
+                "\"0\"",
+                StatusCode::PRECONDITION_FAILED,
+            ),
+            (
+                http::Method::POST,
+                "/dummy/1/restore",
+                &etag,
+                StatusCode::NO_CONTENT,
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request(method, uri, etag))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), status, "{uri}");
+        }
 
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .method(http::Method::PUT)
-                    .uri("/dummy/1")
-                    .header(http::header::CONTENT_TYPE, mime::CSV.as_ref())
-                    .body(body)
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
+        assert!(Dummy::fetch_one(&pool, 1).await.is_ok());
+    }
 
-        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    /// Soft deleted resource whose delete check rejects the name `invalid`.
+    #[derive(Debug, Serialize, Deserialize, FromRow, Crud)]
+    #[crud(table = "flag", id = "id_flag", deleted_at = "deleted_at")]
+    struct Flag {
+        id_flag: i64,
+        name: String,
+    }
+
+    impl Check for Flag {
+        fn check_delete(&self) -> Result<(), Vec<&str>> {
+            match self.name.as_str() {
+                "invalid" => Err(vec!["name must not be invalid"]),
+                _ => Ok(()),
+            }
+        }
     }
 
     #[tokio::test]
-    async fn update_empty() {
-        let pool = database(1).await;
+    async fn purge_check() {
+        let pool = database(0).await;
 
-        let app = router(pool.clone()).await;
+        pool.execute(sqlx::raw_sql(
+            &crate::schema::Table::of::<Flag>().create(crate::dialect::Dialect::Sqlite),
+        ))
+        .await
+        .unwrap();
 
-        let body = "".to_string();
+        let flag = Flag {
+            id_flag: 1,
+            name: "invalid".to_string(),
+        };
+        flag.insert(&pool).await.unwrap();
+        Flag::delete(&pool, 1).await.unwrap();
+
+        let app = Router::new()
+            .route("/flag/:id/purge", delete(crud::purge::<Flag>))
+            .with_state(pool.clone());
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .method(http::Method::PUT)
-                    .uri("/dummy/1")
-                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
-                    .body(body)
+                    .method(http::Method::DELETE)
+                    .uri("/flag/1/purge")
+                    .body("".to_string())
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(Flag::fetch_trashed(&pool, 1).await.is_ok());
     }
 
     #[tokio::test]
-    async fn update_bad_id() {
-        let pool = database(1).await;
+    async fn delete_cascade() {
+        let pool = database(2).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
+        for (method, uri, status) in [
+            (http::Method::DELETE, "/dummy/1", StatusCode::NO_CONTENT),
+            (
+                http::Method::GET,
+                "/dummy/1/subdummy/1",
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                http::Method::POST,
+                "/dummy/1/restore",
+                StatusCode::NO_CONTENT,
+            ),
+            (http::Method::GET, "/dummy/1/subdummy/1", StatusCode::OK),
+            (http::Method::DELETE, "/dummy/1", StatusCode::NO_CONTENT),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .body("".to_string())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), status, "{uri}");
+        }
+
+        // Trashing leaves the children alone, the purge takes them along.
+        assert!(SubDummy::fetch_one(&pool, 1).await.is_ok());
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .method(http::Method::PUT)
-                    .uri("/dummy/a")
-                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
-                    .body(body)
+                    .method(http::Method::DELETE)
+                    .uri("/dummy/1/purge")
+                    .body("".to_string())
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(SubDummy::fetch_one(&pool, 1).await.is_err());
+        assert!(SubDummy::fetch_one(&pool, 2).await.is_ok());
+    }
+
+    #[derive(Debug, Serialize, Deserialize, FromRow, Crud)]
+    #[crud(
+        table = "team",
+        id = "id_team",
+        children(Member = restrict, Badge = set_null)
+    )]
+    struct Team {
+        id_team: i64,
+    }
+
+    impl Check for Team {}
+
+    #[derive(Debug, Serialize, Deserialize, FromRow, Crud)]
+    #[crud(table = "member", id = "id_member", deleted_at = "deleted_at")]
+    struct Member {
+        id_member: i64,
+        #[crud(parent = Team)]
+        id_team: i64,
+    }
+
+    impl Check for Member {}
+
+    #[derive(Debug, Serialize, Deserialize, FromRow, Crud)]
+    #[crud(table = "badge", id = "id_badge")]
+    struct Badge {
+        id_badge: i64,
+        #[crud(parent = Team)]
+        id_team: Option<i64>,
+    }
+
+    impl Check for Badge {}
+
+    #[tokio::test]
+    async fn delete_restrict_set_null() {
+        let pool = database(0).await;
+
+        let _ = pool
+            .execute(sqlx::raw_sql(
+                "CREATE TABLE team (id_team bigint PRIMARY KEY);
+                CREATE TABLE member (id_member bigint PRIMARY KEY, id_team bigint, deleted_at bigint);
+                CREATE TABLE badge (id_badge bigint PRIMARY KEY, id_team bigint);
+                INSERT INTO team VALUES (1), (2);
+                INSERT INTO member VALUES (1, 1, NULL);
+                INSERT INTO badge VALUES (1, 1), (2, 2);",
+            ))
+            .await;
+
+        let app = Router::new()
+            .route("/team/:id", delete(crud::delete::<Team>))
+            .with_state(pool.clone());
+
+        let status = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .method(http::Method::DELETE)
+                        .uri(uri)
+                        .body("".to_string())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+            }
+        };
+
+        assert_eq!(status("/team/1").await, StatusCode::CONFLICT);
+        assert!(Team::fetch_one(&pool, 1).await.is_ok());
+        assert_eq!(Badge::fetch_one(&pool, 1).await.unwrap().id_team, Some(1));
+
+        // A trashed member still references its team until it is purged.
+        Member::delete(&pool, 1).await.unwrap();
+        assert_eq!(status("/team/1").await, StatusCode::CONFLICT);
+
+        Member::purge(&pool, 1).await.unwrap();
+        assert_eq!(status("/team/1").await, StatusCode::NO_CONTENT);
+        assert_eq!(Badge::fetch_one(&pool, 1).await.unwrap().id_team, None);
+
+        assert_eq!(status("/team/2").await, StatusCode::NO_CONTENT);
+        assert!(Team::fetch_one(&pool, 2).await.is_err());
+        assert_eq!(Badge::fetch_one(&pool, 2).await.unwrap().id_team, None);
     }
 
     #[tokio::test]
-    async fn update_bad_json() {
+    async fn update_if_match() {
         let pool = database(1).await;
 
         let app = router(pool.clone()).await;
 
-        let body = r#"{"id_dummy": 1, "name": "name" 123, "is_valid": false}:"#.to_string();
+        let etag = etag::etag(&Dummy::fetch_one(&pool, 1).await.unwrap()).unwrap();
+
+        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
 
         let response = app
             .oneshot(
@@ -789,45 +2348,62 @@ mod tests {
                     .method(http::Method::PUT)
                     .uri("/dummy/1")
                     .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(http::header::IF_MATCH, etag)
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let dummy = Dummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(dummy.name, "name-new");
     }
 
     #[tokio::test]
-    async fn update_inexistent() {
-        let pool = database(0).await;
+    async fn update_etag_if_match() {
+        let pool = database(1).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
+        let put = |etag: Option<String>| {
+            let mut request = Request::builder()
+                .method(http::Method::PUT)
+                .uri("/dummy/1")
+                .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref());
+            if let Some(etag) = etag {
+                request = request.header(http::header::IF_MATCH, etag);
+            }
+            request
+                .body(json!({"id_dummy": 1, "name": "name-new", "is_valid": true}).to_string())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(put(None)).await.unwrap();
 
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .method(http::Method::PUT)
-                    .uri("/dummy/1")
-                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
-                    .body(body)
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
 
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let etag = response.headers()[http::header::ETAG].to_str().unwrap();
+
+        assert_eq!(
+            etag::etag(&Dummy::fetch_one(&pool, 1).await.unwrap()).as_deref(),
+            Some(etag)
+        );
+
+        let response = app.oneshot(put(Some(etag.to_string()))).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
     }
 
     #[tokio::test]
-    async fn update_invalid() {
+    async fn update_precondition_failed() {
         let pool = database(1).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name", "is_valid": false}).to_string();
+        let body = json!({"id_dummy": 1, "name": "name-new"}).to_string();
 
         let response = app
             .oneshot(
@@ -835,17 +2411,38 @@ mod tests {
                     .method(http::Method::PUT)
                     .uri("/dummy/1")
                     .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(http::header::IF_MATCH, "\"0\"")
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(
+            response
+                .headers()
+                .get(http::header::CONTENT_TYPE)
+                .map(|v| v.to_str().unwrap()),
+            Some("application/problem+json")
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(problem["status"], 412);
+        assert_eq!(
+            problem["detail"],
+            "If-Match does not match the current representation"
+        );
+
+        let dummy = Dummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(dummy.name, "name-1");
     }
 
     #[tokio::test]
-    async fn update_sub_ok() {
+    async fn update_sub_precondition_failed() {
         let pool = database(1).await;
 
         let app = router(pool.clone()).await;
@@ -858,101 +2455,105 @@ mod tests {
                     .method(http::Method::PUT)
                     .uri("/dummy/1/subdummy/1")
                     .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(http::header::IF_MATCH, "\"0\"")
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
         let dummy = SubDummy::fetch_one(&pool, 1).await.unwrap();
 
-        assert_eq!(response.status(), StatusCode::OK);
-        assert_eq!(dummy.id_sub_dummy, 1);
-        assert_eq!(dummy.id_dummy, 1);
-        assert_eq!(dummy.name, "name-new");
+        assert_eq!(dummy.name, "name-1");
     }
 
     #[tokio::test]
-    async fn update_sub_not_found() {
+    async fn patch_precondition_failed() {
         let pool = database(1).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name-new", "id_sub_dummy": 1}).to_string();
+        let body = json!({"name": "name-new"}).to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .method(http::Method::PUT)
-                    .uri("/dummy/2/subdummy/1")
-                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .method(http::Method::PATCH)
+                    .uri("/dummy/1")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        crud::APPLICATION_MERGE_PATCH_JSON,
+                    )
+                    .header(http::header::IF_MATCH, "\"0\"")
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        let dummy = SubDummy::fetch_one(&pool, 1).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+        let dummy = Dummy::fetch_one(&pool, 1).await.unwrap();
 
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
-        assert_eq!(dummy.id_sub_dummy, 1);
-        assert_eq!(dummy.id_dummy, 1);
         assert_eq!(dummy.name, "name-1");
     }
 
     #[tokio::test]
-    async fn update_sub_mismatch_data() {
-        let pool = database(2).await;
+    async fn delete_if_match() {
+        let pool = database(1).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 2, "name": "name-new", "id_sub_dummy": 1}).to_string();
+        let etag = etag::etag(&Dummy::fetch_one(&pool, 1).await.unwrap()).unwrap();
+
+        let body = "".to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .method(http::Method::PUT)
-                    .uri("/dummy/1/subdummy/1")
+                    .method(http::Method::DELETE)
+                    .uri("/dummy/1")
                     .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(http::header::IF_MATCH, etag)
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(Dummy::fetch_one(&pool, 1).await.is_err());
     }
 
     #[tokio::test]
-    async fn update_sub_mismatch() {
-        let pool = database(2).await;
+    async fn delete_precondition_failed() {
+        let pool = database(1).await;
 
         let app = router(pool.clone()).await;
 
-        let body = json!({"id_dummy": 1, "name": "name-new", "id_sub_dummy": 1}).to_string();
+        let body = "".to_string();
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .method(http::Method::PUT)
-                    .uri("/dummy/2/subdummy/1")
+                    .method(http::Method::DELETE)
+                    .uri("/dummy/1")
                     .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(http::header::IF_MATCH, "\"0\"")
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        let dummy = SubDummy::fetch_one(&pool, 1).await.unwrap();
-
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
-        assert_eq!(dummy.id_sub_dummy, 1);
-        assert_eq!(dummy.id_dummy, 1);
-        assert_eq!(dummy.name, "name-1");
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        assert!(Dummy::fetch_one(&pool, 1).await.is_ok());
     }
 
     #[tokio::test]
-    async fn delete_ok() {
+    async fn delete_sub_precondition_failed() {
         let pool = database(1).await;
 
         let app = router(pool.clone()).await;
@@ -963,18 +2564,17 @@ mod tests {
             .oneshot(
                 Request::builder()
                     .method(http::Method::DELETE)
-                    .uri("/dummy/1")
+                    .uri("/dummy/1/subdummy/1")
                     .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(http::header::IF_MATCH, "\"0\"")
                     .body(body)
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        let dummy = Dummy::fetch_one(&pool, 1).await;
-
-        assert_eq!(response.status(), StatusCode::NO_CONTENT);
-        assert!(dummy.is_err());
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        assert!(SubDummy::fetch_one(&pool, 1).await.is_ok());
     }
 
     #[tokio::test]
@@ -1126,4 +2726,29 @@ mod tests {
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
         assert!(dummy.is_ok());
     }
+
+    #[tokio::test]
+    async fn transaction_rollback() {
+        let pool = database(1).await;
+
+        let mut tx = pool.begin().await.unwrap();
+
+        let mut dummy = Dummy::fetch_one(&mut tx, 1).await.unwrap();
+        dummy.name = "changed".to_string();
+        dummy.update(&mut tx).await.unwrap();
+        Dummy::delete(&mut tx, 1).await.unwrap();
+
+        assert_eq!(
+            Dummy::count_all(&mut tx, &ListQuery::default())
+                .await
+                .unwrap(),
+            0
+        );
+
+        tx.rollback().await.unwrap();
+
+        let dummy = Dummy::fetch_one(&pool, 1).await.unwrap();
+
+        assert_eq!(dummy.name, "name-1");
+    }
 }
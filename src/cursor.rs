@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::prelude::QueryToken;
+
+/// Position of a keyset scan: the `order` parameter it was started with and the values of
+/// the order keys, id tie-breaker included, of the last row seen. Clients get it as an
+/// opaque hex string and send it back untouched.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<Value>,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        serde_json::to_vec(self)
+            .unwrap_or_default()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// An empty string starts a scan from the first row.
+    pub fn decode(value: &str) -> Option<Self> {
+        if value.is_empty() {
+            return Some(Self::default());
+        }
+
+        if !value.len().is_multiple_of(2) || !value.is_ascii() {
+            return None;
+        }
+
+        let bytes = (0..value.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Keys as bind values, `None` when one of them is not a string, number or bool.
+    pub fn tokens(&self) -> Option<Vec<QueryToken>> {
+        self.keys
+            .iter()
+            .map(|key| match key {
+                Value::String(value) => Some(QueryToken::Text(value.clone())),
+                Value::Number(value) => match value.as_i64() {
+                    Some(value) => Some(QueryToken::Numeric(value)),
+                    None => value.as_f64().map(QueryToken::Float),
+                },
+                Value::Bool(value) => Some(QueryToken::Bool(*value)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn cursor_roundtrip() {
+        let cursor = Cursor {
+            order: Some("-name".to_string()),
+            keys: vec![json!("name-1"), json!(1)],
+        };
+
+        let encoded = cursor.encode();
+
+        assert!(encoded.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(Cursor::decode(&encoded), Some(cursor.clone()));
+        assert_eq!(
+            cursor.tokens(),
+            Some(vec![
+                QueryToken::Text("name-1".to_string()),
+                QueryToken::Numeric(1)
+            ])
+        );
+    }
+
+    #[test]
+    fn cursor_invalid() {
+        assert_eq!(Cursor::decode(""), Some(Cursor::default()));
+        assert_eq!(Cursor::decode("abc"), None);
+        assert_eq!(Cursor::decode("zz"), None);
+        assert_eq!(Cursor::decode("7b"), None);
+
+        let cursor = Cursor {
+            order: None,
+            keys: vec![json!(null)],
+        };
+        assert_eq!(cursor.tokens(), None);
+    }
+}
@@ -0,0 +1,63 @@
+use sqlx::AnyConnection;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Dialect {
+    Sqlite,
+    MySql,
+    Postgres,
+}
+
+impl Dialect {
+    pub fn from_connection(conn: &AnyConnection) -> Self {
+        match conn.backend_name() {
+            "PostgreSQL" => Dialect::Postgres,
+            "MySQL" => Dialect::MySql,
+            _ => Dialect::Sqlite,
+        }
+    }
+
+    pub fn placeholders(self) -> Placeholders {
+        Placeholders {
+            dialect: self,
+            count: 0,
+        }
+    }
+}
+
+/// Hands out bind placeholders in order: `?` for SQLite/MySQL, `$1..$n` for Postgres.
+#[derive(Debug)]
+pub struct Placeholders {
+    dialect: Dialect,
+    count: usize,
+}
+
+impl Placeholders {
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    pub fn bind(&mut self) -> String {
+        self.count += 1;
+
+        match self.dialect {
+            Dialect::Postgres => format!("${}", self.count),
+            Dialect::Sqlite | Dialect::MySql => "?".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders() {
+        let mut placeholders = Dialect::Postgres.placeholders();
+        assert_eq!(placeholders.bind(), "$1");
+        assert_eq!(placeholders.bind(), "$2");
+
+        let mut placeholders = Dialect::Sqlite.placeholders();
+        assert_eq!(placeholders.bind(), "?");
+        assert_eq!(placeholders.bind(), "?");
+    }
+}
@@ -0,0 +1,265 @@
+use std::{collections::BTreeMap, error::Error, fmt};
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use sqlx::error::ErrorKind;
+use validator::ValidationErrors;
+
+pub const APPLICATION_PROBLEM_JSON: &str = "application/problem+json";
+
+/// Response body following RFC 7807 (`application/problem+json`).
+#[derive(Debug, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, Vec<String>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<String>,
+}
+
+impl Problem {
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            kind: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: None,
+            errors: BTreeMap::new(),
+            checks: vec![],
+        }
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+        (
+            status,
+            [(header::CONTENT_TYPE, APPLICATION_PROBLEM_JSON)],
+            Json(self),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Debug)]
+pub enum CrudError {
+    NotFound,
+    UniqueViolation(String),
+    ForeignKeyViolation(String),
+    Serialization(String),
+    Unavailable(String),
+    Validation(ValidationErrors),
+    Check(Vec<String>),
+    InvalidQuery(String),
+    Database(String),
+}
+
+impl fmt::Display for CrudError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrudError::NotFound => write!(f, "resource not found"),
+            CrudError::UniqueViolation(e) => write!(f, "unique constraint violation: {e}"),
+            CrudError::ForeignKeyViolation(e) => write!(f, "foreign key violation: {e}"),
+            CrudError::Serialization(e) => write!(f, "serialization failure: {e}"),
+            CrudError::Unavailable(e) => write!(f, "database unavailable: {e}"),
+            CrudError::Validation(e) => write!(f, "validation failed: {e}"),
+            CrudError::Check(e) => write!(f, "check failed: {}", e.join(", ")),
+            CrudError::InvalidQuery(e) => write!(f, "invalid query: {e}"),
+            CrudError::Database(e) => write!(f, "database error: {e}"),
+        }
+    }
+}
+
+impl Error for CrudError {}
+
+impl From<sqlx::Error> for CrudError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::RowNotFound => CrudError::NotFound,
+            sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::WorkerCrashed => {
+                CrudError::Unavailable(error.to_string())
+            }
+            sqlx::Error::Io(_) | sqlx::Error::Tls(_) => CrudError::Unavailable(error.to_string()),
+            sqlx::Error::Database(ref e) => match e.kind() {
+                ErrorKind::UniqueViolation => CrudError::UniqueViolation(e.message().to_string()),
+                ErrorKind::ForeignKeyViolation => {
+                    CrudError::ForeignKeyViolation(e.message().to_string())
+                }
+                // SQLSTATE 40001 (serialization_failure) and 40P01 (deadlock_detected)
+                _ if matches!(e.code().as_deref(), Some("40001") | Some("40P01")) => {
+                    CrudError::Serialization(e.message().to_string())
+                }
+                _ => CrudError::Database(e.message().to_string()),
+            },
+            _ => CrudError::Database(error.to_string()),
+        }
+    }
+}
+
+impl From<ValidationErrors> for CrudError {
+    fn from(errors: ValidationErrors) -> Self {
+        CrudError::Validation(errors)
+    }
+}
+
+impl From<Vec<&str>> for CrudError {
+    fn from(messages: Vec<&str>) -> Self {
+        CrudError::Check(messages.into_iter().map(String::from).collect())
+    }
+}
+
+impl CrudError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            CrudError::NotFound => StatusCode::NOT_FOUND,
+            CrudError::UniqueViolation(_)
+            | CrudError::ForeignKeyViolation(_)
+            | CrudError::Serialization(_) => StatusCode::CONFLICT,
+            CrudError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            CrudError::Validation(_) | CrudError::Check(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            CrudError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+            CrudError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn problem(&self) -> Problem {
+        let problem = Problem::new(self.status());
+
+        match self {
+            CrudError::NotFound => problem,
+            CrudError::UniqueViolation(_) => {
+                problem.detail("A resource with the same key already exists")
+            }
+            CrudError::ForeignKeyViolation(_) => {
+                problem.detail("The resource references or is referenced by another resource")
+            }
+            CrudError::Serialization(_) => {
+                problem.detail("The request conflicted with a concurrent transaction, retry it")
+            }
+            CrudError::Unavailable(_) => problem.detail("The database is currently unavailable"),
+            CrudError::Validation(errors) => {
+                let mut problem = problem.detail("The request body failed validation");
+                problem.errors = errors
+                    .field_errors()
+                    .into_iter()
+                    .map(|(field, errors)| {
+                        (
+                            field.to_string(),
+                            errors
+                                .iter()
+                                .map(|e| {
+                                    e.message
+                                        .as_ref()
+                                        .map(|m| m.to_string())
+                                        .unwrap_or_else(|| e.code.to_string())
+                                })
+                                .collect(),
+                        )
+                    })
+                    .collect();
+                problem
+            }
+            CrudError::Check(messages) => {
+                let mut problem = problem.detail("The request was rejected by the resource checks");
+                problem.checks = messages.clone();
+                problem
+            }
+            CrudError::InvalidQuery(detail) => problem.detail(detail),
+            CrudError::Database(_) => problem,
+        }
+    }
+}
+
+impl IntoResponse for CrudError {
+    fn into_response(self) -> Response {
+        self.problem().into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, StatusCode};
+    use http_body_util::BodyExt;
+    use serde_json::Value;
+    use validator::Validate;
+
+    use super::*;
+
+    #[derive(Validate)]
+    struct Named {
+        #[validate(length(min = 1))]
+        name: String,
+    }
+
+    #[tokio::test]
+    async fn problem_validation() {
+        let errors = Named {
+            name: "".to_string(),
+        }
+        .validate()
+        .unwrap_err();
+
+        let response = CrudError::from(errors).into_response();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .map(|v| v.to_str().unwrap()),
+            Some(APPLICATION_PROBLEM_JSON)
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(problem["status"], 422);
+        assert_eq!(problem["errors"]["name"][0], "length");
+    }
+
+    #[tokio::test]
+    async fn problem_check() {
+        let response = CrudError::from(vec!["first", "second"]).into_response();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(problem["checks"][0], "first");
+        assert_eq!(problem["checks"][1], "second");
+        assert!(problem.get("errors").is_none());
+    }
+
+    #[test]
+    fn status_from_sqlx() {
+        assert_eq!(
+            CrudError::from(sqlx::Error::RowNotFound).status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            CrudError::from(sqlx::Error::PoolTimedOut).status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            CrudError::from(sqlx::Error::ColumnNotFound("name".to_string())).status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
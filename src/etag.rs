@@ -0,0 +1,79 @@
+use axum::http::{header, HeaderMap, HeaderValue};
+use serde::Serialize;
+
+/// Strong entity tag computed from the JSON serialization of `row` (64-bit FNV-1a).
+pub fn etag<T: Serialize>(row: &T) -> Option<String> {
+    let bytes = serde_json::to_vec(row).ok()?;
+
+    let hash = bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    });
+
+    Some(format!("\"{hash:016x}\""))
+}
+
+fn matches(value: &HeaderValue, etag: &str, weak: bool) -> bool {
+    let Ok(value) = value.to_str() else {
+        return false;
+    };
+
+    value.split(',').map(str::trim).any(|tag| {
+        if tag == "*" {
+            return true;
+        }
+
+        match tag.strip_prefix("W/") {
+            Some(tag) => weak && tag == etag,
+            None => tag == etag,
+        }
+    })
+}
+
+/// `If-Match` precondition, compared strongly. Passes when the header is absent.
+pub fn if_match(headers: &HeaderMap, etag: Option<&str>) -> bool {
+    match headers.get(header::IF_MATCH) {
+        Some(value) => etag.is_some_and(|etag| matches(value, etag, false)),
+        None => true,
+    }
+}
+
+/// `If-None-Match` precondition, compared weakly. Passes when the header is absent.
+pub fn if_none_match(headers: &HeaderMap, etag: Option<&str>) -> bool {
+    match headers.get(header::IF_NONE_MATCH) {
+        Some(value) => !etag.is_some_and(|etag| matches(value, etag, true)),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn etag_stable() {
+        let a = etag(&("name", 1)).unwrap();
+        let b = etag(&("name", 1)).unwrap();
+        let c = etag(&("name", 2)).unwrap();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert!(a.starts_with('"') && a.ends_with('"'));
+    }
+
+    #[test]
+    fn etag_preconditions() {
+        let mut headers = HeaderMap::new();
+        assert!(if_match(&headers, Some("\"a\"")));
+        assert!(if_none_match(&headers, Some("\"a\"")));
+
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("\"b\", \"a\""));
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("W/\"a\""));
+        assert!(if_match(&headers, Some("\"a\"")));
+        assert!(!if_none_match(&headers, Some("\"a\"")));
+
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("W/\"a\""));
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(!if_match(&headers, Some("\"a\"")));
+        assert!(!if_none_match(&headers, Some("\"a\"")));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::{dialect::Placeholders, error::CrudError, prelude::QueryToken};
+
+/// Rust types a filterable column can have. `parse` turns a query string value into a
+/// token bound with the column type, `OPS` are the operators `#[crud(filter)]` allows.
+pub trait FilterValue {
+    const OPS: &'static [FilterOp] = &[
+        FilterOp::Eq,
+        FilterOp::Ne,
+        FilterOp::Gt,
+        FilterOp::Gte,
+        FilterOp::Lt,
+        FilterOp::Lte,
+        FilterOp::In,
+    ];
+
+    fn parse(value: &str) -> Option<QueryToken>;
+}
+
+impl FilterValue for String {
+    const OPS: &'static [FilterOp] = &[
+        FilterOp::Eq,
+        FilterOp::Ne,
+        FilterOp::Gt,
+        FilterOp::Gte,
+        FilterOp::Lt,
+        FilterOp::Lte,
+        FilterOp::Like,
+        FilterOp::In,
+    ];
+
+    fn parse(value: &str) -> Option<QueryToken> {
+        Some(QueryToken::Text(value.to_string()))
+    }
+}
+
+impl FilterValue for i64 {
+    fn parse(value: &str) -> Option<QueryToken> {
+        value.parse().ok().map(QueryToken::Numeric)
+    }
+}
+
+impl FilterValue for i32 {
+    fn parse(value: &str) -> Option<QueryToken> {
+        value
+            .parse::<i32>()
+            .ok()
+            .map(|v| QueryToken::Numeric(v.into()))
+    }
+}
+
+impl FilterValue for f64 {
+    fn parse(value: &str) -> Option<QueryToken> {
+        value.parse().ok().map(QueryToken::Float)
+    }
+}
+
+impl FilterValue for bool {
+    const OPS: &'static [FilterOp] = &[FilterOp::Eq, FilterOp::Ne, FilterOp::In];
+
+    fn parse(value: &str) -> Option<QueryToken> {
+        value.parse().ok().map(QueryToken::Bool)
+    }
+}
+
+impl<T: FilterValue> FilterValue for Option<T> {
+    const OPS: &'static [FilterOp] = T::OPS;
+
+    fn parse(value: &str) -> Option<QueryToken> {
+        T::parse(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Like,
+    In,
+}
+
+impl FilterOp {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "eq" => Some(FilterOp::Eq),
+            "ne" => Some(FilterOp::Ne),
+            "gt" => Some(FilterOp::Gt),
+            "gte" => Some(FilterOp::Gte),
+            "lt" => Some(FilterOp::Lt),
+            "lte" => Some(FilterOp::Lte),
+            "like" => Some(FilterOp::Like),
+            "in" => Some(FilterOp::In),
+            _ => None,
+        }
+    }
+
+    fn operator(self) -> &'static str {
+        match self {
+            FilterOp::Eq => "=",
+            FilterOp::Ne => "<>",
+            FilterOp::Gt => ">",
+            FilterOp::Gte => ">=",
+            FilterOp::Lt => "<",
+            FilterOp::Lte => "<=",
+            FilterOp::Like => "LIKE",
+            FilterOp::In => "IN",
+        }
+    }
+}
+
+/// A column that can be filtered on, with the operators it accepts.
+#[derive(Debug, Clone, Copy)]
+pub struct FilterField {
+    pub name: &'static str,
+    pub parse: fn(&str) -> Option<QueryToken>,
+    pub ops: &'static [FilterOp],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub field: &'static str,
+    pub op: FilterOp,
+    pub values: Vec<QueryToken>,
+}
+
+impl Filter {
+    /// Parses the `filter[field][op]=value` pairs of a query string against `fields`.
+    /// `filter[field]=value` is short for `eq`, `in` takes a comma separated list and pairs
+    /// not starting with `filter[` are ignored.
+    pub fn parse(
+        fields: &[FilterField],
+        pairs: &[(String, String)],
+    ) -> Result<Vec<Self>, CrudError> {
+        let mut filters = vec![];
+
+        for (key, value) in pairs {
+            let Some(rest) = key.strip_prefix("filter[") else {
+                continue;
+            };
+
+            let invalid = || CrudError::InvalidQuery(format!("Invalid filter `{key}`"));
+
+            let (name, rest) = rest.split_once(']').ok_or_else(invalid)?;
+            let op = match rest {
+                "" => FilterOp::Eq,
+                _ => rest
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .and_then(FilterOp::from_name)
+                    .ok_or_else(invalid)?,
+            };
+
+            let Some(field) = fields
+                .iter()
+                .find(|f| f.name == name && f.ops.contains(&op))
+            else {
+                return Err(CrudError::InvalidQuery(format!(
+                    "Filter `{key}` is not allowed"
+                )));
+            };
+
+            let values = match op {
+                FilterOp::In => value.split(',').map(|v| (field.parse)(v.trim())).collect(),
+                _ => (field.parse)(value).map(|v| vec![v]),
+            };
+
+            match values {
+                Some(values) if !values.is_empty() => filters.push(Filter {
+                    field: field.name,
+                    op,
+                    values,
+                }),
+                _ => {
+                    return Err(CrudError::InvalidQuery(format!(
+                        "Invalid value `{value}` for filter `{key}`"
+                    )))
+                }
+            }
+        }
+
+        Ok(filters)
+    }
+
+    pub fn sql(&self, placeholders: &mut Placeholders) -> String {
+        match self.op {
+            FilterOp::In => format!(
+                "{} IN ({})",
+                self.field,
+                self.values
+                    .iter()
+                    .map(|_| placeholders.bind())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            op => format!("{} {} {}", self.field, op.operator(), placeholders.bind()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dialect::Dialect;
+
+    use super::*;
+
+    const FIELDS: &[FilterField] = &[
+        FilterField {
+            name: "name",
+            parse: String::parse,
+            ops: &[FilterOp::Eq, FilterOp::Like],
+        },
+        FilterField {
+            name: "id",
+            parse: i64::parse,
+            ops: &[FilterOp::Gte, FilterOp::In],
+        },
+        FilterField {
+            name: "valid",
+            parse: <Option<bool>>::parse,
+            ops: &[FilterOp::In],
+        },
+    ];
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn filter_parse() {
+        let filters = Filter::parse(
+            FIELDS,
+            &pairs(&[
+                ("filter[name]", "x"),
+                ("filter[id][gte]", "10"),
+                ("filter[valid][in]", "true,false"),
+                ("limit", "5"),
+            ]),
+        )
+        .unwrap();
+
+        let mut placeholders = Dialect::Postgres.placeholders();
+        let sql = filters
+            .iter()
+            .map(|f| f.sql(&mut placeholders))
+            .collect::<Vec<_>>();
+
+        assert_eq!(sql, ["name = $1", "id >= $2", "valid IN ($3, $4)"]);
+        assert_eq!(
+            filters[2].values,
+            [QueryToken::Bool(true), QueryToken::Bool(false)]
+        );
+    }
+
+    #[test]
+    fn filter_invalid() {
+        for (key, value) in [
+            ("filter[name][gte]", "x"),
+            ("filter[other]", "x"),
+            ("filter[id][gte]", "ten"),
+            ("filter[id][between]", "1"),
+            ("filter[id", "1"),
+        ] {
+            let result = Filter::parse(FIELDS, &pairs(&[(key, value)]));
+
+            assert!(
+                matches!(result, Err(CrudError::InvalidQuery(_))),
+                "{key}={value}"
+            );
+        }
+    }
+}
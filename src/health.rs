@@ -0,0 +1,310 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Serialize;
+
+use crate::{error::Problem, migrate, router::Pool, schema::Table};
+
+/// Whether the server takes traffic: set once it serves, cleared as soon as shutdown begins.
+#[derive(Debug, Clone, Default)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    pub fn set(&self, ready: bool) {
+        self.0.store(ready, Ordering::SeqCst);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// What the probes check, shared with them as an extension.
+#[derive(Debug, Clone)]
+pub struct Health {
+    pub readiness: Readiness,
+    pub tables: Arc<[Table]>,
+    /// Time each database check may take.
+    pub timeout: Duration,
+}
+
+/// Outcome of one check, the error explaining a failure.
+#[derive(Debug, Serialize)]
+struct Check {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Check {
+    /// Runs `check`, failing it when it does not finish within `timeout`.
+    async fn run(timeout: Duration, check: impl Future<Output = Result<(), String>>) -> Self {
+        let result = match tokio::time::timeout(timeout, check).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("timed out after {timeout:?}")),
+        };
+
+        Self {
+            ok: result.is_ok(),
+            error: result.err(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PoolReport {
+    size: u32,
+    idle: usize,
+    max: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    ready: bool,
+    database: Check,
+    migrations: Check,
+    pool: PoolReport,
+    tables: BTreeMap<&'static str, Check>,
+}
+
+/// A connection is acquired and answers `SELECT 1`.
+async fn database(pool: &Pool) -> Result<(), String> {
+    sqlx::query("SELECT 1")
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Every migration of the dialect is applied.
+async fn migrations(pool: &Pool) -> Result<(), String> {
+    let pending = migrate::pending(pool).await.map_err(|e| e.to_string())?;
+
+    match pending.as_slice() {
+        [] => Ok(()),
+        pending => Err(format!(
+            "pending migrations: {}",
+            pending
+                .iter()
+                .map(|m| m.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// The table can be read, without fetching any row.
+async fn table(pool: &Pool, table: &Table) -> Result<(), String> {
+    sqlx::query(&format!("SELECT 1 FROM {} WHERE 1 = 0", table.name))
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn unavailable(detail: impl Into<String>) -> Response {
+    Problem::new(StatusCode::SERVICE_UNAVAILABLE)
+        .detail(detail)
+        .into_response()
+}
+
+/// `GET /healthz`, answering while the process runs.
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// `GET /readyz`, failing before the server is up, once it shuts down and while the
+/// database is unreachable or not fully migrated.
+pub async fn readyz(State(pool): State<Pool>, Extension(health): Extension<Health>) -> Response {
+    if !health.readiness.is_ready() {
+        return unavailable("The server is not ready");
+    }
+
+    for check in [
+        Check::run(health.timeout, database(&pool)).await,
+        Check::run(health.timeout, migrations(&pool)).await,
+    ] {
+        if let Some(error) = check.error {
+            return unavailable(error);
+        }
+    }
+
+    (StatusCode::OK, "ready").into_response()
+}
+
+/// `GET /health`, the readiness checks in detail along with the pool usage and whether each
+/// resource table can be read. Unavailable unless every check passes.
+pub async fn health(State(pool): State<Pool>, Extension(health): Extension<Health>) -> Response {
+    let database = Check::run(health.timeout, database(&pool)).await;
+    let migrations = Check::run(health.timeout, migrations(&pool)).await;
+
+    let mut tables = BTreeMap::new();
+    for t in health.tables.iter() {
+        tables.insert(t.name, Check::run(health.timeout, table(&pool, t)).await);
+    }
+
+    let report = Report {
+        ready: health.readiness.is_ready(),
+        database,
+        migrations,
+        pool: PoolReport {
+            size: pool.size(),
+            idle: pool.num_idle(),
+            max: pool.options().get_max_connections(),
+        },
+        tables,
+    };
+
+    let ok = report.ready
+        && report.database.ok
+        && report.migrations.ok
+        && report.tables.values().all(|check| check.ok);
+    let status = match ok {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status, Json(report)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use serde_json::{json, Value};
+    use sqlx::any::AnyPoolOptions;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::types::{dummy::Dummy, sub_dummy::SubDummy};
+
+    async fn database() -> Pool {
+        sqlx::any::install_default_drivers();
+        AnyPoolOptions::new()
+            .max_connections(1) // needs to be 1, otherwise memory database is gone
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    fn app(pool: Pool, readiness: &Readiness) -> Router {
+        Router::new()
+            .route("/healthz", get(healthz))
+            .route("/readyz", get(readyz))
+            .route("/health", get(super::health))
+            .with_state(pool)
+            .layer(Extension(Health {
+                readiness: readiness.clone(),
+                tables: Arc::from([Table::of::<Dummy>(), Table::of::<SubDummy>()]),
+                timeout: Duration::from_secs(1),
+            }))
+    }
+
+    async fn get_json(app: Router, uri: &str) -> (StatusCode, Value) {
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn healthz_always_ok() {
+        let app = app(database().await, &Readiness::default());
+
+        assert_eq!(get_json(app, "/healthz").await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn readyz_checks() {
+        let pool = database().await;
+        let readiness = Readiness::default();
+
+        let (status, problem) = get_json(app(pool.clone(), &readiness), "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(problem["detail"], "The server is not ready");
+
+        readiness.set(true);
+        assert_eq!(
+            get_json(app(pool.clone(), &readiness), "/readyz").await.0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        migrate::up(&pool).await.unwrap();
+        assert_eq!(
+            get_json(app(pool.clone(), &readiness), "/readyz").await.0,
+            StatusCode::OK
+        );
+
+        migrate::down(&pool).await.unwrap();
+        let (status, problem) = get_json(app(pool.clone(), &readiness), "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            problem["detail"],
+            "pending migrations: 0002_create_sub_dummy"
+        );
+
+        readiness.set(false);
+        assert_eq!(
+            get_json(app(pool, &readiness), "/readyz").await.0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[tokio::test]
+    async fn health_report() {
+        let pool = database().await;
+        let readiness = Readiness::default();
+        readiness.set(true);
+        migrate::up(&pool).await.unwrap();
+
+        let (status, report) = get_json(app(pool.clone(), &readiness), "/health").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["ready"], true);
+        assert_eq!(report["database"], json!({"ok": true}));
+        assert_eq!(report["migrations"], json!({"ok": true}));
+        assert_eq!(
+            report["tables"],
+            json!({"dummy": {"ok": true}, "sub_dummy": {"ok": true}})
+        );
+        // Connections go back to the pool in the background, so idle lags behind.
+        assert_eq!(report["pool"]["size"], 1);
+        assert_eq!(report["pool"]["max"], 1);
+        assert!(report["pool"]["idle"].as_u64().unwrap() <= 1);
+
+        sqlx::query("DROP TABLE sub_dummy")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let (status, report) = get_json(app(pool, &readiness), "/health").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(report["tables"]["dummy"], json!({"ok": true}));
+        assert_eq!(report["tables"]["sub_dummy"]["ok"], false);
+        assert!(report["tables"]["sub_dummy"]["error"]
+            .as_str()
+            .unwrap()
+            .contains("no such table"));
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    error::CrudError,
+    prelude::{
+        decode_field, Connect, DatabaseFetchAll, Include, MatchParent, SelectField, Trashed,
+    },
+    router::{Db, Pool},
+};
+
+/// Key the parent of a row is embedded under.
+pub const PARENT: &str = "parent";
+
+/// Most children embedded per row, the rest is left to the nested list endpoint.
+pub const INCLUDE_LIMIT: i64 = 10;
+
+/// A resource `?include=` can embed, and the column of the row it is looked up by.
+#[derive(Debug, Clone, Copy)]
+pub struct IncludeField {
+    pub name: &'static str,
+    pub field: &'static str,
+}
+
+/// `fields` plus the columns `includes` are looked up by, whether or not they were asked
+/// for. [`retain`] drops them again once the resources are embedded.
+pub fn projection(fields: &[SelectField], includes: &[IncludeField]) -> Vec<SelectField> {
+    let mut projection = fields.to_vec();
+
+    for include in includes {
+        if !projection.iter().any(|field| field.name == include.field) {
+            projection.push(SelectField {
+                name: include.field,
+                decode: decode_field::<Option<i64>>,
+            });
+        }
+    }
+
+    projection
+}
+
+/// Embeds each of `includes` into `rows`, one query per include.
+pub async fn embed<T>(
+    pool: &Pool,
+    includes: &[IncludeField],
+    rows: &mut [Map<String, Value>],
+) -> Result<(), CrudError>
+where
+    T: Include<Db>,
+{
+    for include in includes {
+        T::include(pool, include.name, rows).await?;
+    }
+
+    Ok(())
+}
+
+/// Embeds under [`PARENT`] the row of `P` that `field` of each of `rows` points to.
+pub async fn parent<P>(
+    db: impl Connect<Db>,
+    field: &str,
+    rows: &mut [Map<String, Value>],
+) -> Result<(), CrudError>
+where
+    P: DatabaseFetchAll<Db> + Serialize,
+{
+    let parents = P::fetch_in(db, P::FIELD_ID, &ids(rows, field), None, Trashed::Without).await?;
+    let parents = group(&parents, P::FIELD_ID)?;
+
+    for row in rows {
+        let parent = link(row, field)
+            .and_then(|id| parents.get(&id))
+            .and_then(|parents| parents.first())
+            .cloned()
+            .unwrap_or_default();
+
+        row.insert(PARENT.to_string(), parent);
+    }
+
+    Ok(())
+}
+
+/// Embeds under the table name of `C` the first [`INCLUDE_LIMIT`] children of each of
+/// `rows`, whose id is `field`.
+pub async fn children<C, P>(
+    db: impl Connect<Db>,
+    field: &str,
+    rows: &mut [Map<String, Value>],
+) -> Result<(), CrudError>
+where
+    C: DatabaseFetchAll<Db> + MatchParent<Db, Parent = P> + Serialize,
+{
+    let children = C::fetch_in(
+        db,
+        C::FIELD_PARENT,
+        &ids(rows, field),
+        Some(INCLUDE_LIMIT),
+        Trashed::Without,
+    )
+    .await?;
+    let mut children = group(&children, C::FIELD_PARENT)?;
+
+    for row in rows {
+        let children = link(row, field)
+            .and_then(|id| children.remove(&id))
+            .unwrap_or_default();
+
+        row.insert(C::TABLE.to_string(), Value::Array(children));
+    }
+
+    Ok(())
+}
+
+/// `v` as JSON objects, for rows that get resources embedded.
+pub fn objects<T: Serialize>(v: &[T]) -> Result<Vec<Map<String, Value>>, CrudError> {
+    v.iter()
+        .map(|row| match serde_json::to_value(row) {
+            Ok(Value::Object(row)) => Ok(row),
+            Ok(_) => Err(CrudError::Database("Row is not an object".to_string())),
+            Err(e) => Err(CrudError::Database(e.to_string())),
+        })
+        .collect()
+}
+
+/// Drops the columns of sparse rows that were only read to look the includes up.
+pub fn retain(rows: &mut [Map<String, Value>], fields: &[SelectField], includes: &[IncludeField]) {
+    for row in rows {
+        row.retain(|name, _| {
+            fields.iter().any(|field| field.name == name)
+                || includes.iter().any(|include| include.name == name)
+        });
+    }
+}
+
+fn link(row: &Map<String, Value>, field: &str) -> Option<i64> {
+    row.get(field).and_then(Value::as_i64)
+}
+
+fn ids(rows: &[Map<String, Value>], field: &str) -> Vec<i64> {
+    let mut ids = rows
+        .iter()
+        .filter_map(|row| link(row, field))
+        .collect::<Vec<_>>();
+
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+fn group<T: Serialize>(v: &[T], field: &str) -> Result<HashMap<i64, Vec<Value>>, CrudError> {
+    let mut groups = HashMap::<_, Vec<_>>::new();
+
+    for row in objects(v)? {
+        if let Some(id) = link(&row, field) {
+            groups.entry(id).or_default().push(Value::Object(row));
+        }
+    }
+
+    Ok(groups)
+}
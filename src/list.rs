@@ -1,11 +1,18 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
     response::{IntoResponse, Response},
+    Extension,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{router::Pool, Database, DatabaseFetchAll, MatchParent};
+use crate::{
+    error::Problem,
+    include,
+    router::{Db, Pool},
+    CrudError, Cursor, Database, DatabaseFetchAll, Include, ListQuery, MatchParent, OrderKey,
+    SelectField, Trashed, RANK,
+};
 
 #[derive(Deserialize)]
 pub struct QueryParams {
@@ -13,81 +20,428 @@ pub struct QueryParams {
     order: Option<String>,
     offset: Option<i64>,
     limit: Option<i64>,
+    /// Switches to keyset pagination, empty for the first page.
+    cursor: Option<String>,
+    /// Comma separated columns to return instead of all of them.
+    fields: Option<String>,
+    /// Comma separated related resources to embed in every row.
+    include: Option<String>,
+    /// Whether soft deleted rows are listed, left out by default.
+    #[serde(default)]
+    trashed: Trashed,
+    #[serde(default)]
+    envelope: bool,
+}
+
+/// `Accept: application/json; profile="envelope"` asks for the same body as `?envelope=true`.
+const ENVELOPE_PROFILE: &str = "envelope";
+
+#[derive(Serialize)]
+struct Envelope<T> {
+    data: Vec<T>,
+    meta: Meta,
+}
+
+#[derive(Serialize)]
+struct Meta {
+    total: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<i64>,
+    limit: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
+}
+
+pub const DEFAULT_LIMIT: i64 = 50;
+pub const MAX_LIMIT: i64 = 250;
+
+/// Page sizes of a list endpoint, set by the router from the paging configuration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub default: i64,
+    pub max: i64,
 }
 
-const DEFAULT_LIMIT: i64 = 50;
-const MAX_LIMIT: i64 = 250;
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_LIMIT,
+            max: MAX_LIMIT,
+        }
+    }
+}
 
 pub async fn list<T>(
+    uri: Uri,
     State(pool): State<Pool>,
     parent_id: Option<Path<i64>>,
+    limits: Option<Extension<Limits>>,
+    headers: HeaderMap,
     Query(query): Query<QueryParams>,
 ) -> Response
 where
-    T: Database<Pool> + DatabaseFetchAll<Pool> + Serialize,
+    T: Database<Db> + DatabaseFetchAll<Db> + Include<Db> + Serialize,
 {
+    let limits = limits.map(|Extension(limits)| limits).unwrap_or_default();
     let offset = query.offset.unwrap_or(0);
-    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    let limit = query.limit.unwrap_or(limits.default);
+
+    if offset < 0 || limit <= 0 || limit > limits.max {
+        return Problem::new(StatusCode::BAD_REQUEST)
+            .detail(format!(
+                "Offset must not be negative and limit must be between 1 and {}",
+                limits.max
+            ))
+            .into_response();
+    }
 
-    if offset < 0 || limit <= 0 || limit > MAX_LIMIT {
-        StatusCode::BAD_REQUEST.into_response();
+    let cursor = match query.cursor.as_deref().map(Cursor::decode) {
+        Some(Some(cursor)) if cursor.keys.is_empty() => Some(Cursor {
+            order: query.order.clone(),
+            ..cursor
+        }),
+        Some(Some(cursor)) => Some(cursor),
+        Some(None) => return invalid_cursor(),
+        None => None,
+    };
+
+    let order = match &cursor {
+        Some(cursor) => T::order(cursor.order.as_deref()),
+        None => T::order(query.order.as_deref()),
+    };
+    let order = match order {
+        Ok(order) => order,
+        Err(e) => return e.into_response(),
+    };
+
+    if cursor.is_some() && order.iter().any(|key| key.field == RANK) {
+        return CrudError::InvalidQuery(format!(
+            "Ordering by `{RANK}` cannot be combined with a cursor"
+        ))
+        .into_response();
+    }
+
+    // A NULL key cannot be written into the cursor nor compared in the keyset predicate.
+    let nullable = |key: &&OrderKey| {
+        T::COLUMNS
+            .iter()
+            .any(|column| column.name == key.field && column.nullable)
+    };
+    if let Some(key) = order.iter().find(|key| cursor.is_some() && nullable(key)) {
+        return CrudError::InvalidQuery(format!(
+            "Ordering by the nullable `{}` cannot be combined with a cursor",
+            key.field
+        ))
+        .into_response();
     }
 
-    let total = T::count(&pool).await;
-    match total {
+    let after = match cursor.as_ref().map(Cursor::tokens) {
+        Some(Some(after)) if after.is_empty() || after.len() == order.len() => after,
+        Some(_) => return invalid_cursor(),
+        None => vec![],
+    };
+
+    if query.trashed != Trashed::Without && T::FIELD_DELETED_AT.is_empty() {
+        return CrudError::InvalidQuery(format!("`{}` has no trash to list", T::TABLE))
+            .into_response();
+    }
+
+    let pairs = Query::<Vec<(String, String)>>::try_from_uri(&uri)
+        .map(|Query(pairs)| pairs)
+        .unwrap_or_default();
+
+    let search = match T::search(query.search.as_deref().unwrap_or_default()) {
+        Ok(search) => search,
+        Err(e) => return e.into_response(),
+    };
+
+    let filters = match T::filters(&pairs) {
+        Ok(filters) => filters,
+        Err(e) => return e.into_response(),
+    };
+
+    let fields = match query.fields.as_deref().map(T::select).transpose() {
+        Ok(fields) => fields,
+        Err(e) => return e.into_response(),
+    };
+
+    // The next cursor is read off the last row, so keyset scans also select the order keys.
+    let projection = match &fields {
+        Some(fields) if cursor.is_some() => match projection::<T>(fields, &order) {
+            Ok(projection) => Some(projection),
+            Err(e) => return e.into_response(),
+        },
+        fields => fields.clone(),
+    };
+
+    let includes = match query.include.as_deref().map(T::includes).transpose() {
+        Ok(includes) => includes.unwrap_or_default(),
+        Err(e) => return e.into_response(),
+    };
+    let projection = projection.map(|projection| include::projection(&projection, &includes));
+
+    let offset = if cursor.is_some() { 0 } else { offset };
+
+    let list = ListQuery {
+        search,
+        trashed: query.trashed,
+        filters,
+        order,
+        parent_id: parent_id.map(|Path(v)| v),
+        after,
+        offset,
+        limit,
+    };
+
+    let total = match T::count_all(&pool, &list).await {
         Ok(total) if total <= 0 => {
             return StatusCode::NOT_FOUND.into_response();
         }
-        Err(_) => {
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        Ok(total) => total,
+        Err(e) => {
+            return e.into_response();
         }
-        _ => {}
-    }
+    };
 
-    let list = T::fetch_all(
-        &pool,
-        query.search,
-        query.order,
-        parent_id.map(|Path(v)| v),
+    let paging = Paging {
+        uri: &uri,
+        envelope: query.envelope || accepts_envelope(&headers),
+        keyset: cursor.is_some(),
         offset,
         limit,
-    )
-    .await;
-    match list {
-        Ok(v) if v.len() > 0 => (
+        max_limit: limits.max,
+        total,
+    };
+
+    match projection {
+        Some(projection) => match T::fetch_all_fields(&pool, &list, &projection).await {
+            Ok(mut v) if !v.is_empty() => {
+                let next = next_cursor(cursor.as_ref(), &list.order, &v, limit);
+
+                if let Err(e) = include::embed::<T>(&pool, &includes, &mut v).await {
+                    return e.into_response();
+                }
+                include::retain(&mut v, &fields.unwrap_or_default(), &includes);
+
+                paging.response(v, next)
+            }
+            Ok(_) => StatusCode::NOT_FOUND.into_response(),
+            Err(e) => e.into_response(),
+        },
+        None => match T::fetch_all(&pool, &list).await {
+            Ok(v) if !v.is_empty() => {
+                let next = next_cursor(cursor.as_ref(), &list.order, &v, limit);
+                if includes.is_empty() {
+                    return paging.response(v, next);
+                }
+
+                let mut v = match include::objects(&v) {
+                    Ok(v) => v,
+                    Err(e) => return e.into_response(),
+                };
+                match include::embed::<T>(&pool, &includes, &mut v).await {
+                    Ok(()) => paging.response(v, next),
+                    Err(e) => e.into_response(),
+                }
+            }
+            Ok(_) => StatusCode::NOT_FOUND.into_response(),
+            Err(e) => e.into_response(),
+        },
+    }
+}
+
+struct Paging<'a> {
+    uri: &'a Uri,
+    envelope: bool,
+    keyset: bool,
+    offset: i64,
+    limit: i64,
+    max_limit: i64,
+    total: i64,
+}
+
+impl Paging<'_> {
+    /// A non empty page with its paging headers and links, `next` being the keyset cursor of
+    /// the following page.
+    fn response<R: Serialize>(self, v: Vec<R>, next: Option<String>) -> Response {
+        let Paging {
+            uri,
+            envelope,
+            keyset,
+            offset,
+            limit,
+            max_limit,
+            total,
+        } = self;
+
+        let size = v.len() as i64;
+        let mut links = vec![];
+
+        if keyset {
+            links.push(("first", link(uri, "cursor", "")));
+            if let Some(next) = &next {
+                links.push(("next", link(uri, "cursor", next)));
+            }
+        } else {
+            let last = (total - 1) / limit * limit;
+
+            links.push(("first", link(uri, "offset", "0")));
+            if offset > 0 {
+                let prev = (offset - limit).max(0).min(last);
+                links.push(("prev", link(uri, "offset", &prev.to_string())));
+            }
+            if offset + size < total {
+                let next = offset + size;
+                links.push(("next", link(uri, "offset", &next.to_string())));
+            }
+            links.push(("last", link(uri, "offset", &last.to_string())));
+        }
+
+        let body = if envelope {
+            let meta = Meta {
+                total,
+                offset: (!keyset).then_some(offset),
+                limit,
+                next: next.clone(),
+            };
+            serde_json::to_string(&Envelope { data: v, meta })
+        } else {
+            serde_json::to_string(&v)
+        };
+
+        let mut response = (
             StatusCode::OK,
-            [("X-Paging-MaxLimit", format!("{}", MAX_LIMIT))],
-            [("X-Paging-Total", format!("{}", total.unwrap_or(0)))],
-            [("X-Paging-Size", format!("{}", v.len()))],
-            serde_json::to_string(&v).unwrap_or(String::new()),
+            [("X-Paging-MaxLimit", format!("{}", max_limit))],
+            [("X-Paging-Total", format!("{}", total))],
+            [("X-Paging-Size", format!("{}", size))],
+            body.unwrap_or(String::new()),
         )
-            .into_response(),
-        Ok(_) => StatusCode::NOT_FOUND.into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            .into_response();
+
+        let headers = response.headers_mut();
+        if let Some(value) = next.and_then(|next| HeaderValue::from_str(&next).ok()) {
+            headers.insert("X-Paging-Next", value);
+        }
+
+        let links = links
+            .into_iter()
+            .map(|(rel, url)| format!("<{url}>; rel=\"{rel}\""))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if let Ok(value) = HeaderValue::from_str(&links) {
+            headers.insert(header::LINK, value);
+        }
+
+        response
+    }
+}
+
+fn projection<T>(fields: &[SelectField], order: &[OrderKey]) -> Result<Vec<SelectField>, CrudError>
+where
+    T: DatabaseFetchAll<Db>,
+{
+    let mut projection = fields.to_vec();
+
+    for key in order {
+        if !projection.iter().any(|field| field.name == key.field) {
+            projection.extend(T::select(key.field)?);
+        }
     }
+
+    Ok(projection)
+}
+
+fn invalid_cursor() -> Response {
+    Problem::new(StatusCode::BAD_REQUEST)
+        .detail("The cursor is invalid")
+        .into_response()
+}
+
+/// Encoded cursor pointing after the last row of a full page, with the keys taken from its
+/// serialized `order` columns. `None` when one of them is missing or null.
+fn next_cursor<T>(
+    cursor: Option<&Cursor>,
+    order: &[OrderKey],
+    v: &[T],
+    limit: i64,
+) -> Option<String>
+where
+    T: Serialize,
+{
+    let cursor = cursor.filter(|_| v.len() as i64 == limit)?;
+    let row = serde_json::to_value(v.last()?).ok()?;
+
+    let next = Cursor {
+        order: cursor.order.clone(),
+        keys: order
+            .iter()
+            .map(|key| row.get(key.field).filter(|value| !value.is_null()).cloned())
+            .collect::<Option<_>>()?,
+    };
+
+    Some(next.encode())
+}
+
+/// Request URI with the paging parameter `name` set to `value`. `cursor` and `offset` are
+/// mutually exclusive, so both are dropped from the original query first.
+fn link(uri: &Uri, name: &str, value: &str) -> String {
+    let pair = format!("{name}={value}");
+
+    let query = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter(|pair| !pair.starts_with("cursor=") && !pair.starts_with("offset="))
+        .chain([pair.as_str()])
+        .collect::<Vec<_>>()
+        .join("&");
+
+    format!("{}?{}", uri.path(), query)
+}
+
+fn accepts_envelope(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split([',', ';']))
+        .filter_map(|param| param.trim().strip_prefix("profile="))
+        .any(|profile| profile.trim_matches('"') == ENVELOPE_PROFILE)
 }
 
 pub async fn sub_list<T>(
+    uri: Uri,
     State(pool): State<Pool>,
     Path(parent_id): Path<i64>,
+    limits: Option<Extension<Limits>>,
+    headers: HeaderMap,
     Query(query): Query<QueryParams>,
 ) -> Response
 where
-    T: Database<Pool> + DatabaseFetchAll<Pool> + MatchParent<Pool> + Serialize,
-    T::Parent: Database<Pool>,
+    T: Database<Db> + DatabaseFetchAll<Db> + Include<Db> + MatchParent<Db> + Serialize,
+    T::Parent: Database<Db>,
 {
-    if T::Parent::fetch_one(&pool, parent_id).await.is_err() {
-        return StatusCode::NOT_FOUND.into_response();
+    if let Err(e) = T::Parent::fetch_one(&pool, parent_id).await {
+        return e.into_response();
     }
 
-    list::<T>(State(pool), Some(Path(parent_id)), Query(query)).await
+    list::<T>(
+        uri,
+        State(pool),
+        Some(Path(parent_id)),
+        limits,
+        headers,
+        Query(query),
+    )
+    .await
 }
 
 #[cfg(test)]
 mod tests {
     use axum::{
         http::{self, Request, StatusCode},
-        routing::get,
+        routing::{get, put},
         Router,
     };
 
@@ -96,8 +450,10 @@ mod tests {
         types::{dummy::Dummy, sub_dummy::SubDummy},
     };
     use http_body_util::BodyExt;
-    use sqlx::{any::AnyPoolOptions, Any, Executor, Pool};
+    use serde::{Deserialize, Serialize};
+    use sqlx::{any::AnyPoolOptions, Any, Executor, FromRow, Pool};
     use tower::ServiceExt;
+    use validator::Validate;
 
     async fn database(size: i64) -> Pool<Any> {
         sqlx::any::install_default_drivers();
@@ -107,17 +463,7 @@ mod tests {
             .await
             .unwrap();
 
-        let _ = pool
-            .execute(sqlx::raw_sql(
-                "CREATE TABLE dummy (id_dummy bigint, name text);",
-            ))
-            .await;
-
-        let _ = pool
-            .execute(sqlx::raw_sql(
-                "CREATE TABLE sub_dummy (id_sub_dummy bigint, name text, id_dummy bigint);",
-            ))
-            .await;
+        crate::migrate::up(&pool).await.unwrap();
 
         for i in 1..=size {
             let _ = Dummy::insert(
@@ -382,7 +728,7 @@ mod tests {
             .oneshot(
                 Request::builder()
                     .method(http::Method::GET)
-                    .uri("/dummy/?search=-9")
+                    .uri("/dummy/?search=%22-9%22")
                     .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
                     .body(body)
                     .unwrap(),
@@ -406,6 +752,68 @@ mod tests {
         )
     }
 
+    #[tokio::test]
+    async fn list_search_terms() {
+        let pool = database(20).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?search=name:%22name-1%22%20-name:5%20-id_dummy:1")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let dummies: Vec<Dummy> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            dummies
+                .into_iter()
+                .map(|r| r.id_dummy)
+                .collect::<Vec<i64>>(),
+            [10, 11, 12, 13, 14, 16, 17, 18, 19]
+        )
+    }
+
+    #[tokio::test]
+    async fn list_search_invalid() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?search=is_valid:true")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(problem["detail"], "Unknown search field `is_valid`");
+    }
+
     #[tokio::test]
     async fn list_order() {
         let pool = database(10).await;
@@ -442,6 +850,68 @@ mod tests {
         )
     }
 
+    #[tokio::test]
+    async fn list_order_desc() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?order=name:desc,id_dummy")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let dummies: Vec<Dummy> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            dummies
+                .into_iter()
+                .map(|r| r.id_dummy)
+                .collect::<Vec<i64>>(),
+            [9, 8, 7, 6, 5, 4, 3, 2, 10, 1]
+        )
+    }
+
+    #[tokio::test]
+    async fn list_order_invalid() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?order=-is_valid")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(problem["detail"], "Ordering by `is_valid` is not allowed");
+    }
+
     #[tokio::test]
     async fn list_sub_ok() {
         let pool = database(10).await;
@@ -505,4 +975,1035 @@ mod tests {
             [(1, 1)]
         )
     }
+
+    #[tokio::test]
+    async fn list_search_paging_total() {
+        let pool = database(100).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?search=name-1&limit=5")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get("X-Paging-Total")
+                .map(|v| v.to_str().unwrap()),
+            Some("12")
+        );
+        assert_eq!(
+            response
+                .headers()
+                .get("X-Paging-Size")
+                .map(|v| v.to_str().unwrap()),
+            Some("5")
+        );
+    }
+
+    #[tokio::test]
+    async fn list_sub_paging_total() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/1/sub_dummy/")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get("X-Paging-Total")
+                .map(|v| v.to_str().unwrap()),
+            Some("1")
+        );
+    }
+
+    #[tokio::test]
+    async fn list_sub_empty() {
+        let pool = database(10).await;
+
+        let _ = Dummy::insert(
+            &(Dummy {
+                id_dummy: 11,
+                name: "name-11".to_string(),
+                is_valid: Some(true),
+            }),
+            &pool,
+        )
+        .await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/11/sub_dummy/")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn list_cursor() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let mut names = vec![];
+        let mut cursor = "".to_string();
+
+        for _ in 0..4 {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(http::Method::GET)
+                        .uri(format!("/dummy/?order=name&limit=3&cursor={cursor}"))
+                        .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                        .body("".to_string())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let next = response
+                .headers()
+                .get("X-Paging-Next")
+                .map(|v| v.to_str().unwrap().to_string());
+            let link = response
+                .headers()
+                .get(http::header::LINK)
+                .map(|v| v.to_str().unwrap().to_string());
+
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let dummies: Vec<Dummy> = serde_json::from_slice(&body).unwrap();
+
+            names.extend(dummies.into_iter().map(|r| r.name));
+
+            match next {
+                Some(next) => {
+                    assert_eq!(
+                        link,
+                        Some(format!(
+                            "</dummy/?order=name&limit=3&cursor=>; rel=\"first\", </dummy/?order=name&limit=3&cursor={next}>; rel=\"next\""
+                        ))
+                    );
+                    cursor = next;
+                }
+                None => {
+                    assert_eq!(
+                        link.as_deref(),
+                        Some("</dummy/?order=name&limit=3&cursor=>; rel=\"first\"")
+                    );
+                    break;
+                }
+            }
+        }
+
+        let mut expected = (1..=10).map(|i| format!("name-{i}")).collect::<Vec<_>>();
+        expected.sort();
+
+        assert_eq!(names, expected);
+    }
+
+    #[tokio::test]
+    async fn list_cursor_desc() {
+        let pool = database(10).await;
+
+        for i in 11..=14 {
+            let _ = Dummy::insert(
+                &(Dummy {
+                    id_dummy: i,
+                    name: "same".to_string(),
+                    is_valid: Some(true),
+                }),
+                &pool,
+            )
+            .await;
+        }
+
+        let app = router(pool.clone()).await;
+
+        let mut ids = vec![];
+        let mut cursor = "".to_string();
+
+        loop {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(http::Method::GET)
+                        .uri(format!("/dummy/?order=-name&limit=4&cursor={cursor}"))
+                        .body("".to_string())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let next = response
+                .headers()
+                .get("X-Paging-Next")
+                .map(|v| v.to_str().unwrap().to_string());
+
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let dummies: Vec<Dummy> = serde_json::from_slice(&body).unwrap();
+
+            ids.extend(dummies.into_iter().map(|r| r.id_dummy));
+
+            match next {
+                Some(next) => cursor = next,
+                None => break,
+            }
+        }
+
+        assert_eq!(ids, [11, 12, 13, 14, 9, 8, 7, 6, 5, 4, 3, 2, 10, 1]);
+    }
+
+    #[tokio::test]
+    async fn list_fields() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?fields=name&limit=2")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let dummies: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            dummies,
+            serde_json::json!([{"name": "name-1"}, {"name": "name-2"}])
+        );
+    }
+
+    #[tokio::test]
+    async fn list_fields_cursor() {
+        let pool = database(5).await;
+
+        let app = router(pool.clone()).await;
+
+        let mut names = vec![];
+        let mut cursor = "".to_string();
+
+        loop {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(http::Method::GET)
+                        .uri(format!(
+                            "/dummy/?fields=name&order=-id_dummy&limit=2&cursor={cursor}"
+                        ))
+                        .body("".to_string())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let next = response
+                .headers()
+                .get("X-Paging-Next")
+                .map(|v| v.to_str().unwrap().to_string());
+
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let dummies: Vec<serde_json::Map<String, serde_json::Value>> =
+                serde_json::from_slice(&body).unwrap();
+
+            for dummy in dummies {
+                assert_eq!(dummy.len(), 1);
+                names.push(dummy["name"].as_str().unwrap().to_string());
+            }
+
+            match next {
+                Some(next) => cursor = next,
+                None => break,
+            }
+        }
+
+        assert_eq!(names, ["name-5", "name-4", "name-3", "name-2", "name-1"]);
+    }
+
+    #[tokio::test]
+    async fn list_fields_not_allowed() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?fields=id_dummy,is_valid")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(problem["detail"], "Field `is_valid` cannot be selected");
+    }
+
+    #[tokio::test]
+    async fn list_include_parent() {
+        let pool = database(3).await;
+
+        let app = router(pool.clone()).await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/2/sub_dummy/?include=parent")
+                    .body("".to_string())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let sub_dummies: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(sub_dummies[0]["name"], "sub-name-2");
+        assert_eq!(
+            sub_dummies[0]["parent"],
+            serde_json::json!({"id_dummy": 2, "name": "name-2", "is_valid": null})
+        );
+    }
+
+    #[tokio::test]
+    async fn list_include_children() {
+        let pool = database(3).await;
+
+        for i in 100..112 {
+            let _ = SubDummy::insert(
+                &(SubDummy {
+                    id_sub_dummy: i,
+                    id_dummy: 1,
+                    name: format!("sub-name-{}", i),
+                    is_valid: Some(true),
+                }),
+                &pool,
+            )
+            .await;
+        }
+
+        let app = router(pool.clone()).await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?fields=name&include=sub_dummy")
+                    .body("".to_string())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let dummies: Vec<serde_json::Map<String, serde_json::Value>> =
+            serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(dummies[0].keys().collect::<Vec<_>>(), ["name", "sub_dummy"]);
+
+        let ids = |dummy: &serde_json::Map<String, serde_json::Value>| {
+            dummy["sub_dummy"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|sub_dummy| sub_dummy["id_sub_dummy"].as_i64().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ids(&dummies[0]),
+            [1, 100, 101, 102, 103, 104, 105, 106, 107, 108]
+        );
+        assert_eq!(ids(&dummies[1]), [2]);
+        assert_eq!(ids(&dummies[2]), [3]);
+    }
+
+    #[tokio::test]
+    async fn list_include_invalid() {
+        let pool = database(3).await;
+
+        let app = router(pool.clone()).await;
+
+        for (uri, detail) in [
+            ("/dummy/?include=other", "`other` cannot be included"),
+            ("/dummy/?include=parent", "`parent` cannot be included"),
+            (
+                "/dummy/1/sub_dummy/?include=sub_dummy",
+                "`sub_dummy` cannot be included",
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(http::Method::GET)
+                        .uri(uri)
+                        .body("".to_string())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+            assert_eq!(problem["detail"], detail);
+        }
+    }
+
+    #[tokio::test]
+    async fn list_trashed() {
+        let pool = database(4).await;
+
+        Dummy::delete(&pool, 2).await.unwrap();
+
+        let app = router(pool.clone()).await;
+
+        for (uri, ids) in [
+            ("/dummy/", vec![1, 3, 4]),
+            ("/dummy/?trashed=only", vec![2]),
+            ("/dummy/?trashed=with", vec![1, 2, 3, 4]),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(http::Method::GET)
+                        .uri(uri)
+                        .body("".to_string())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK, "{uri}");
+            assert_eq!(
+                response.headers().get("X-Paging-Total").unwrap(),
+                &ids.len().to_string(),
+                "{uri}"
+            );
+
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let dummies: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+
+            assert_eq!(
+                dummies
+                    .iter()
+                    .map(|dummy| dummy["id_dummy"].as_i64().unwrap())
+                    .collect::<Vec<_>>(),
+                ids,
+                "{uri}"
+            );
+        }
+
+        for (uri, status) in [
+            ("/dummy/2/sub_dummy/", StatusCode::NOT_FOUND),
+            ("/dummy/1/sub_dummy/?trashed=only", StatusCode::BAD_REQUEST),
+            ("/dummy/?trashed=maybe", StatusCode::BAD_REQUEST),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(http::Method::GET)
+                        .uri(uri)
+                        .body("".to_string())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), status, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn list_cursor_invalid() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?cursor=nothex")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn list_offset_no_cursor() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?limit=5")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("X-Paging-Next").is_none());
+    }
+
+    #[tokio::test]
+    async fn list_links() {
+        let pool = database(100).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?offset=15&limit=10")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(http::header::LINK)
+                .map(|v| v.to_str().unwrap()),
+            Some(
+                "</dummy/?limit=10&offset=0>; rel=\"first\", \
+                 </dummy/?limit=10&offset=5>; rel=\"prev\", \
+                 </dummy/?limit=10&offset=25>; rel=\"next\", \
+                 </dummy/?limit=10&offset=90>; rel=\"last\""
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn list_links_last_page() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?limit=10")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(http::header::LINK)
+                .map(|v| v.to_str().unwrap()),
+            Some(
+                "</dummy/?limit=10&offset=0>; rel=\"first\", \
+                 </dummy/?limit=10&offset=0>; rel=\"last\""
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn list_envelope() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?offset=2&limit=5&envelope=true")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let envelope: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(envelope["data"].as_array().map(Vec::len), Some(5));
+        assert_eq!(
+            envelope["meta"],
+            serde_json::json!({"total": 10, "offset": 2, "limit": 5})
+        );
+    }
+
+    #[tokio::test]
+    async fn list_envelope_profile() {
+        let pool = database(10).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/")
+                    .header(
+                        http::header::ACCEPT,
+                        "application/json; profile=\"envelope\"",
+                    )
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let envelope: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(envelope["data"].as_array().map(Vec::len), Some(10));
+        assert_eq!(envelope["meta"]["total"], 10);
+    }
+
+    #[tokio::test]
+    async fn list_filter() {
+        let pool = database(20).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?filter%5Bid_dummy%5D%5Bgte%5D=10&filter[id_dummy][lt]=15&filter[name][ne]=name-12")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get("X-Paging-Total")
+                .map(|v| v.to_str().unwrap()),
+            Some("4")
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let dummies: Vec<Dummy> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            dummies.into_iter().map(|r| r.id_dummy).collect::<Vec<_>>(),
+            [10, 11, 13, 14]
+        );
+    }
+
+    #[tokio::test]
+    async fn list_filter_in() {
+        let pool = database(20).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?filter[name][in]=name-3,name-7,name-30")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let dummies: Vec<Dummy> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            dummies.into_iter().map(|r| r.id_dummy).collect::<Vec<_>>(),
+            [3, 7]
+        );
+    }
+
+    #[tokio::test]
+    async fn list_filter_not_allowed() {
+        let pool = database(20).await;
+
+        let app = router(pool.clone()).await;
+
+        let body = "".to_string();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/dummy/?filter[name][gte]=name-3")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            problem["detail"],
+            "Filter `filter[name][gte]` is not allowed"
+        );
+    }
+
+    #[derive(Debug, Serialize, Deserialize, FromRow, Crud)]
+    #[crud(table = "score", id = "id_score")]
+    struct Score {
+        #[crud(order)]
+        id_score: i64,
+        #[crud(order)]
+        points: Option<i64>,
+    }
+
+    #[tokio::test]
+    async fn list_cursor_nullable_order() {
+        let pool = database(0).await;
+
+        let _ = pool
+            .execute(sqlx::raw_sql(
+                "CREATE TABLE score (id_score bigint PRIMARY KEY, points bigint);
+                INSERT INTO score VALUES (1, 10), (2, NULL), (3, 5);",
+            ))
+            .await;
+
+        let app = Router::new()
+            .route("/score/", get(super::list::<Score>))
+            .with_state(pool);
+
+        for (uri, status) in [
+            ("/score/?order=points&cursor=", StatusCode::BAD_REQUEST),
+            ("/score/?order=points&limit=1", StatusCode::OK),
+            ("/score/?order=-id_score&cursor=", StatusCode::OK),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(http::Method::GET)
+                        .uri(uri)
+                        .body("".to_string())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), status, "{uri}");
+
+            if status == StatusCode::BAD_REQUEST {
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+                assert_eq!(
+                    problem["detail"],
+                    "Ordering by the nullable `points` cannot be combined with a cursor"
+                );
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Validate, FromRow, Crud)]
+    #[crud(table = "article", id = "id_article", full_text_table = "article_fts")]
+    struct Article {
+        #[crud(order)]
+        id_article: i64,
+        #[crud(search_text, order)]
+        title: String,
+    }
+
+    impl Check for Article {}
+
+    async fn full_text_router() -> axum::Router {
+        let pool = database(0).await;
+
+        let _ = pool
+            .execute(sqlx::raw_sql(
+                &crate::schema::Table::of::<Article>().create(Dialect::Sqlite),
+            ))
+            .await;
+
+        for (id, title) in [
+            (1, "rust web framework"),
+            (2, "rust rust rust"),
+            (3, "python web framework"),
+            (
+                4,
+                "a long book about rust and the web servers written in it",
+            ),
+        ] {
+            let _ = Article::insert(
+                &Article {
+                    id_article: id,
+                    title: title.to_string(),
+                },
+                &pool,
+            )
+            .await;
+        }
+
+        Router::new()
+            .route(
+                "/article/",
+                get(super::list::<Article>).post(crate::crud::create::<Article>),
+            )
+            .route(
+                "/article/:id_article",
+                put(crate::crud::update::<Article>).delete(crate::crud::delete::<Article>),
+            )
+            .with_state(pool)
+    }
+
+    #[tokio::test]
+    async fn list_full_text() {
+        let app = full_text_router().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/article/?search=rust%20-python%20-framework&order=_rank")
+                    .body("".to_string())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get("X-Paging-Total")
+                .map(|v| v.to_str().unwrap()),
+            Some("2")
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let articles: Vec<Article> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            articles
+                .into_iter()
+                .map(|a| a.id_article)
+                .collect::<Vec<_>>(),
+            [2, 4]
+        );
+    }
+
+    /// The index follows the writes made through the API, with nothing filling it by hand.
+    #[tokio::test]
+    async fn list_full_text_writes() {
+        let app = full_text_router().await;
+
+        let search = |app: Router, term: &str| {
+            let uri = format!("/article/?search={term}");
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method(http::Method::GET)
+                            .uri(uri)
+                            .body("".to_string())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+
+                match response.status() {
+                    StatusCode::OK => {
+                        let body = response.into_body().collect().await.unwrap().to_bytes();
+                        serde_json::from_slice::<Vec<Article>>(&body)
+                            .unwrap()
+                            .into_iter()
+                            .map(|a| a.id_article)
+                            .collect::<Vec<_>>()
+                    }
+                    _ => vec![],
+                }
+            }
+        };
+
+        for (method, uri, title) in [
+            (http::Method::POST, "/article/", "haskell compilers"),
+            (http::Method::PUT, "/article/5", "ocaml compilers"),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                        .body(serde_json::json!({"id_article": 5, "title": title}).to_string())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert!(response.status().is_success());
+        }
+
+        assert!(search(app.clone(), "haskell").await.is_empty());
+        assert_eq!(search(app.clone(), "ocaml").await, [5]);
+        assert_eq!(search(app.clone(), "compilers").await, [5]);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::DELETE)
+                    .uri("/article/5")
+                    .body("".to_string())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(search(app, "ocaml").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn list_full_text_invalid() {
+        let app = full_text_router().await;
+
+        for uri in [
+            "/article/?search=-rust",
+            "/article/?search=rust&order=_rank&cursor=",
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(http::Method::GET)
+                        .uri(uri)
+                        .body("".to_string())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+        }
+    }
 }
@@ -1,31 +1,140 @@
+mod bulk;
+mod config;
 mod crud;
+mod cursor;
+mod dialect;
+mod error;
+mod etag;
+mod filter;
+mod health;
+mod include;
 mod list;
+mod migrate;
+mod order;
 mod prelude;
 mod router;
+mod schema;
+mod search;
+mod shutdown;
 mod types;
 
-use std::env;
+use std::{env, fmt, process};
 
+use axum::Extension;
+use config::Config;
+use health::{Health, Readiness};
 use prelude::*;
-use sqlx::any::AnyPoolOptions;
+use tower_http::trace::TraceLayer;
+
+const USAGE: &str = "usage: axum_crud [--migrate] | axum_crud migrate <up|down|status> | axum_crud schema <sqlite|postgres|mysql>";
 
 #[tokio::main]
 async fn main() {
-    let Ok(database_url) = env::var("DATABASE_URL") else {
-        panic!("DATABASE_URL not set");
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    // Checked first, so a mistyped command fails before the database is touched.
+    if !matches!(
+        args.as_slice(),
+        [] | ["--migrate"] | ["migrate", "up" | "down" | "status"] | ["schema", _]
+    ) {
+        exit(USAGE);
+    }
+
+    let config = Config::load().unwrap_or_else(|e| exit(e));
+    tracing_subscriber::fmt()
+        .with_max_level(config.log.level())
+        .init();
+    for key in &config.ignored_env {
+        tracing::warn!("Ignoring {key}, which matches no setting");
+    }
+
+    let resources = router::router(&config);
+    if let Err(e) = config.check_resources(resources.tables()) {
+        exit(e);
+    }
+
+    if let ["schema", dialect] = args.as_slice() {
+        if let Err(e) = schema::command(resources.tables(), dialect) {
+            exit(e);
+        }
+        return;
+    }
+
+    let Some(database_url) = config.database.url.as_deref() else {
+        exit("database.url is not set, in the configuration file or DATABASE_URL");
     };
 
     sqlx::any::install_default_drivers();
-    let Ok(pool) = AnyPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await
-    else {
-        panic!("Cannot connect to the database");
+    let pool = match config.database.pool_options().connect(database_url).await {
+        Ok(pool) => pool,
+        Err(e) => exit(format!("Cannot connect to the database: {e}")),
     };
 
-    let app = crate::router::router().with_state(pool);
+    if let ["migrate", command] = args.as_slice() {
+        if let Err(e) = migrate::command(&pool, command).await {
+            exit(format!("Migration failed: {e}"));
+        }
+        return;
+    }
+
+    if args == ["--migrate"] || config.features.migrate {
+        if let Err(e) = migrate::up(&pool).await {
+            exit(format!("Migration failed: {e}"));
+        }
+    }
+
+    if config.features.verify_schema {
+        match schema::verify(&pool, resources.tables()).await {
+            Ok(diff) if diff.is_empty() => {}
+            Ok(diff) => exit(format!(
+                "Schema does not match the resources:\n{}",
+                diff.join("\n")
+            )),
+            Err(e) => exit(format!("Cannot verify the schema: {e}")),
+        }
+    }
+
+    let readiness = Readiness::default();
+    let health = Health {
+        readiness: readiness.clone(),
+        tables: resources.tables().into(),
+        timeout: config.health.timeout(),
+    };
+    let mut app = resources
+        .into_router()
+        .with_state(pool.clone())
+        .layer(Extension(health))
+        .layer(TraceLayer::new_for_http());
+    if let Some(cors) = config.cors.layer() {
+        app = app.layer(cors);
+    }
+
+    let listener = match tokio::net::TcpListener::bind(config.server.bind).await {
+        Ok(listener) => listener,
+        Err(e) => exit(format!("Cannot bind {}: {e}", config.server.bind)),
+    };
+    tracing::info!("Listening on {}", config.server.bind);
+
+    let served = shutdown::serve(
+        listener,
+        app,
+        readiness,
+        config.server.pre_stop_delay(),
+        config.server.drain_timeout(),
+        shutdown::signal(),
+    )
+    .await;
+
+    pool.close().await;
+    if let Err(e) = served {
+        exit(format!("Server failed: {e}"));
+    }
+    tracing::info!("Stopped");
+}
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+/// Reports a startup failure and stops with a non zero status.
+fn exit(error: impl fmt::Display) -> ! {
+    eprintln!("{error}");
+    process::exit(1)
 }
@@ -0,0 +1,290 @@
+use sqlx::{Connection, Row};
+
+use crate::{
+    dialect::Dialect,
+    error::CrudError,
+    router::{Db, Pool},
+};
+
+/// Table recording the applied migrations, one row per version.
+pub const TABLE: &str = "schema_migrations";
+
+/// A versioned schema change, with the SQL applying and reverting it.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+/// `migrations/<dialect>/<name>.up.sql` and `.down.sql`, embedded in the binary.
+macro_rules! migration {
+    ($dialect:literal, $version:literal, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            up: include_str!(concat!("../migrations/", $dialect, "/", $name, ".up.sql")),
+            down: include_str!(concat!("../migrations/", $dialect, "/", $name, ".down.sql")),
+        }
+    };
+}
+
+const SQLITE: &[Migration] = &[
+    migration!("sqlite", 1, "0001_create_dummy"),
+    migration!("sqlite", 2, "0002_create_sub_dummy"),
+];
+
+const POSTGRES: &[Migration] = &[
+    migration!("postgres", 1, "0001_create_dummy"),
+    migration!("postgres", 2, "0002_create_sub_dummy"),
+];
+
+const MYSQL: &[Migration] = &[
+    migration!("mysql", 1, "0001_create_dummy"),
+    migration!("mysql", 2, "0002_create_sub_dummy"),
+];
+
+/// Migrations of `dialect`, oldest first.
+pub fn migrations(dialect: Dialect) -> &'static [Migration] {
+    match dialect {
+        Dialect::Sqlite => SQLITE,
+        Dialect::Postgres => POSTGRES,
+        Dialect::MySql => MYSQL,
+    }
+}
+
+/// Applies the pending migrations in order, each in its own transaction, and returns their
+/// versions.
+pub async fn up(pool: &Pool) -> Result<Vec<i64>, CrudError> {
+    let mut conn = pool.acquire().await?;
+    let dialect = Dialect::from_connection(&conn);
+    let applied = applied(&mut conn).await?;
+
+    let mut versions = vec![];
+
+    for migration in migrations(dialect) {
+        if applied.contains(&migration.version) {
+            continue;
+        }
+
+        let mut placeholders = dialect.placeholders();
+        let sql = format!(
+            "INSERT INTO {TABLE} (version, name) VALUES ({}, {})",
+            placeholders.bind(),
+            placeholders.bind()
+        );
+
+        let mut tx = conn.begin().await?;
+        sqlx::raw_sql(migration.up).execute(&mut *tx).await?;
+        sqlx::query(&sql)
+            .bind(migration.version)
+            .bind(migration.name)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        versions.push(migration.version);
+    }
+
+    Ok(versions)
+}
+
+/// Reverts the latest applied migration, `None` when there is none.
+pub async fn down(pool: &Pool) -> Result<Option<i64>, CrudError> {
+    let mut conn = pool.acquire().await?;
+    let dialect = Dialect::from_connection(&conn);
+
+    let Some(version) = applied(&mut conn).await?.last().copied() else {
+        return Ok(None);
+    };
+
+    let Some(migration) = migrations(dialect).iter().find(|m| m.version == version) else {
+        return Err(CrudError::Database(format!(
+            "Applied migration {version} is unknown to this build"
+        )));
+    };
+
+    let mut tx = conn.begin().await?;
+    sqlx::raw_sql(migration.down).execute(&mut *tx).await?;
+    sqlx::query(&format!(
+        "DELETE FROM {TABLE} WHERE version = {}",
+        dialect.placeholders().bind()
+    ))
+    .bind(version)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Some(version))
+}
+
+/// Every migration of the database dialect, with whether it is applied.
+pub async fn status(pool: &Pool) -> Result<Vec<(Migration, bool)>, CrudError> {
+    let mut conn = pool.acquire().await?;
+    let dialect = Dialect::from_connection(&conn);
+    let applied = applied(&mut conn).await?;
+
+    Ok(migrations(dialect)
+        .iter()
+        .map(|migration| (*migration, applied.contains(&migration.version)))
+        .collect())
+}
+
+/// Applied versions in ascending order, creating the versions table on first use.
+async fn applied(conn: &mut <Db as sqlx::Database>::Connection) -> Result<Vec<i64>, CrudError> {
+    sqlx::raw_sql(&format!(
+        "CREATE TABLE IF NOT EXISTS {TABLE} (version bigint PRIMARY KEY, name varchar(255) NOT NULL)"
+    ))
+    .execute(&mut *conn)
+    .await?;
+
+    versions(conn).await
+}
+
+async fn versions(conn: &mut <Db as sqlx::Database>::Connection) -> Result<Vec<i64>, CrudError> {
+    let rows = sqlx::query(&format!("SELECT version FROM {TABLE} ORDER BY version"))
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| row.try_get(0))
+        .collect::<Result<_, _>>()?)
+}
+
+/// Migrations not applied yet, without creating the versions table, so it fails on a
+/// database never migrated.
+pub async fn pending(pool: &Pool) -> Result<Vec<Migration>, CrudError> {
+    let mut conn = pool.acquire().await?;
+    let dialect = Dialect::from_connection(&conn);
+    let applied = versions(&mut conn).await?;
+
+    Ok(migrations(dialect)
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .copied()
+        .collect())
+}
+
+/// Runs the `migrate` subcommand `command`, one of `up`, `down` and `status`.
+pub async fn command(pool: &Pool, command: &str) -> Result<(), CrudError> {
+    match command {
+        "up" => match up(pool).await?.as_slice() {
+            [] => println!("Nothing to migrate"),
+            versions => versions
+                .iter()
+                .for_each(|version| println!("Applied {version}")),
+        },
+        "down" => match down(pool).await? {
+            Some(version) => println!("Reverted {version}"),
+            None => println!("Nothing to revert"),
+        },
+        "status" => {
+            for (migration, applied) in status(pool).await? {
+                let state = if applied { "applied" } else { "pending" };
+                println!("{state:<8} {}", migration.name);
+            }
+        }
+        _ => {
+            return Err(CrudError::InvalidQuery(format!(
+                "Unknown migrate command `{command}`, expected up, down or status"
+            )))
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::any::AnyPoolOptions;
+
+    use super::*;
+
+    async fn database() -> Pool {
+        sqlx::any::install_default_drivers();
+        AnyPoolOptions::new()
+            .max_connections(1) // needs to be 1, otherwise memory database is gone
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn migrate_dialects() {
+        let versions = |dialect| {
+            migrations(dialect)
+                .iter()
+                .map(|m| (m.version, m.name))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(versions(Dialect::Sqlite), versions(Dialect::Postgres));
+        assert_eq!(versions(Dialect::Sqlite), versions(Dialect::MySql));
+
+        for dialect in [Dialect::Sqlite, Dialect::Postgres, Dialect::MySql] {
+            assert!(migrations(dialect)
+                .windows(2)
+                .all(|w| w[0].version < w[1].version));
+        }
+    }
+
+    #[tokio::test]
+    async fn migrate_up_down() {
+        let pool = database().await;
+
+        assert_eq!(up(&pool).await.unwrap(), [1, 2]);
+        assert!(up(&pool).await.unwrap().is_empty());
+
+        let applied = |status: Vec<(Migration, bool)>| {
+            status
+                .into_iter()
+                .map(|(m, applied)| (m.version, applied))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            applied(status(&pool).await.unwrap()),
+            [(1, true), (2, true)]
+        );
+
+        assert_eq!(down(&pool).await.unwrap(), Some(2));
+        assert_eq!(
+            applied(status(&pool).await.unwrap()),
+            [(1, true), (2, false)]
+        );
+        assert!(sqlx::query("SELECT * FROM sub_dummy")
+            .fetch_all(&pool)
+            .await
+            .is_err());
+
+        assert_eq!(down(&pool).await.unwrap(), Some(1));
+        assert_eq!(down(&pool).await.unwrap(), None);
+
+        assert_eq!(up(&pool).await.unwrap(), [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn migrate_pending() {
+        let pool = database().await;
+
+        assert!(pending(&pool).await.is_err());
+
+        status(&pool).await.unwrap();
+        assert_eq!(pending(&pool).await.unwrap().len(), 2);
+
+        up(&pool).await.unwrap();
+        assert!(pending(&pool).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn migrate_command_unknown() {
+        let pool = database().await;
+
+        assert!(matches!(
+            command(&pool, "sideways").await,
+            Err(CrudError::InvalidQuery(_))
+        ));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{dialect::Dialect, error::CrudError};
+
+/// Pseudo column ordering full-text search results by relevance, best match first.
+pub const RANK: &str = "_rank";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nulls {
+    First,
+    Last,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderKey {
+    pub field: &'static str,
+    pub desc: bool,
+    pub nulls: Option<Nulls>,
+}
+
+impl OrderKey {
+    pub fn asc(field: &'static str) -> Self {
+        Self {
+            field,
+            desc: false,
+            nulls: None,
+        }
+    }
+
+    /// Parses a comma separated list of keys allowed by `fields`. A key is descending when
+    /// prefixed with `-` or suffixed with `:desc`, and takes `:nulls_first` / `:nulls_last`.
+    ///
+    /// `-name,id` and `name:desc:nulls_last,id:asc` are both valid. [`RANK`] is always
+    /// accepted, it is up to the caller to reject it when there is nothing to rank.
+    pub fn parse(fields: &[&'static str], order: &str) -> Result<Vec<Self>, CrudError> {
+        let mut keys = vec![];
+
+        for item in order.split(',').map(str::trim) {
+            let invalid = || CrudError::InvalidQuery(format!("Invalid order `{item}`"));
+
+            let (desc, item) = match item.strip_prefix('-') {
+                Some(item) => (true, item),
+                None => (false, item.trim_start_matches('+')),
+            };
+
+            let mut parts = item.split(':');
+            let name = parts.next().unwrap_or_default();
+
+            let Some(field) = fields.iter().chain([&RANK]).find(|f| **f == name) else {
+                return Err(CrudError::InvalidQuery(format!(
+                    "Ordering by `{name}` is not allowed"
+                )));
+            };
+
+            let mut key = Self {
+                field,
+                desc,
+                nulls: None,
+            };
+
+            for part in parts {
+                match part {
+                    "asc" if !desc => key.desc = false,
+                    "desc" if !desc => key.desc = true,
+                    "nulls_first" => key.nulls = Some(Nulls::First),
+                    "nulls_last" => key.nulls = Some(Nulls::Last),
+                    _ => return Err(invalid()),
+                }
+            }
+
+            if keys.iter().any(|k: &Self| k.field == key.field) {
+                return Err(invalid());
+            }
+
+            keys.push(key);
+        }
+
+        Ok(keys)
+    }
+
+    /// MySQL has no `NULLS FIRST/LAST`, so the null check is sorted on first instead.
+    pub fn sql(&self, dialect: Dialect) -> String {
+        let direction = if self.desc { " DESC" } else { "" };
+
+        match (self.nulls, dialect) {
+            (None, _) => format!("{}{direction}", self.field),
+            (Some(nulls), Dialect::MySql) => {
+                let first = if nulls == Nulls::First { " DESC" } else { "" };
+                format!("{} IS NULL{first}, {}{direction}", self.field, self.field)
+            }
+            (Some(Nulls::First), _) => format!("{}{direction} NULLS FIRST", self.field),
+            (Some(Nulls::Last), _) => format!("{}{direction} NULLS LAST", self.field),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: &[&str] = &["id", "name", "size"];
+
+    #[test]
+    fn order_parse() {
+        let keys = OrderKey::parse(FIELDS, "-name, size:desc:nulls_last,id:asc").unwrap();
+
+        assert_eq!(
+            keys,
+            [
+                OrderKey {
+                    field: "name",
+                    desc: true,
+                    nulls: None
+                },
+                OrderKey {
+                    field: "size",
+                    desc: true,
+                    nulls: Some(Nulls::Last)
+                },
+                OrderKey::asc("id"),
+            ]
+        );
+    }
+
+    #[test]
+    fn order_invalid() {
+        for order in ["other", "name:up", "-name:asc", "name,name", "", "name,"] {
+            assert!(
+                matches!(
+                    OrderKey::parse(FIELDS, order),
+                    Err(CrudError::InvalidQuery(_))
+                ),
+                "{order}"
+            );
+        }
+    }
+
+    #[test]
+    fn order_sql() {
+        let key = OrderKey {
+            field: "size",
+            desc: true,
+            nulls: Some(Nulls::First),
+        };
+
+        assert_eq!(key.sql(Dialect::Postgres), "size DESC NULLS FIRST");
+        assert_eq!(key.sql(Dialect::MySql), "size IS NULL DESC, size DESC");
+        assert_eq!(OrderKey::asc("id").sql(Dialect::Sqlite), "id");
+    }
+}
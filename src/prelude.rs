@@ -1,14 +1,150 @@
-use std::error::Error;
+use std::{
+    future::Future,
+    ops::DerefMut,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub use axum_crud_derive::Crud;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{
+    any::{AnyArguments, AnyRow},
+    pool::PoolConnection,
+    query::{Query, QueryAs, QueryScalar},
+    Any, AnyConnection, Pool, Transaction,
+};
+
+pub use crate::cursor::Cursor;
+pub use crate::dialect::{Dialect, Placeholders};
+pub use crate::error::CrudError;
+pub use crate::filter::{Filter, FilterField, FilterOp, FilterValue};
+pub use crate::include::IncludeField;
+pub use crate::order::{OrderKey, RANK};
+pub use crate::schema::{Column, ColumnType, Reference, SqlType, TEXT_SEARCH_CONFIG};
+pub use crate::search::{SearchFields, SearchTerm};
+
+/// Anything the trait methods can run on: a pool, a pooled connection, a bare connection
+/// or a transaction. Unlike `sqlx::Acquire` it has no lifetime parameter, which keeps the
+/// handler futures `Send` when called with a `&Pool`.
+pub trait Connect<DB>
+where
+    Self: Send,
+    DB: sqlx::Database,
+{
+    type Connection: DerefMut<Target = DB::Connection> + Send;
+
+    fn connect(self) -> impl Future<Output = Result<Self::Connection, CrudError>> + Send;
+}
+
+impl<DB> Connect<DB> for &Pool<DB>
+where
+    DB: sqlx::Database,
+{
+    type Connection = PoolConnection<DB>;
+
+    async fn connect(self) -> Result<Self::Connection, CrudError> {
+        Ok(self.acquire().await?)
+    }
+}
+
+impl<'a, DB> Connect<DB> for &'a mut PoolConnection<DB>
+where
+    DB: sqlx::Database,
+{
+    type Connection = &'a mut DB::Connection;
+
+    async fn connect(self) -> Result<Self::Connection, CrudError> {
+        Ok(&mut **self)
+    }
+}
+
+impl<'a, DB> Connect<DB> for &'a mut Transaction<'_, DB>
+where
+    DB: sqlx::Database,
+{
+    type Connection = &'a mut DB::Connection;
+
+    async fn connect(self) -> Result<Self::Connection, CrudError> {
+        Ok(&mut **self)
+    }
+}
+
+impl<'a> Connect<sqlx::Any> for &'a mut AnyConnection {
+    type Connection = &'a mut AnyConnection;
+
+    async fn connect(self) -> Result<Self::Connection, CrudError> {
+        Ok(self)
+    }
+}
+
+/// Seconds since the Unix epoch, what a soft delete stores in `FIELD_DELETED_AT`.
+pub fn epoch() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
 
 pub trait Database<DB>
 where
     Self: Sized,
+    DB: sqlx::Database,
 {
-    async fn insert(&self, pool: &DB) -> Result<i64, impl Error>;
-    async fn update(&self, pool: &DB) -> Result<(), impl Error>;
-    async fn delete(pool: &DB, id: i64) -> Result<(), impl Error>;
-    async fn fetch_one(pool: &DB, id: i64) -> Result<Self, impl Error>;
-    async fn count(pool: &DB) -> Result<i64, impl Error>;
+    const TABLE: &'static str;
+    const FIELD_ID: &'static str;
+    /// Column holding when a row was deleted, see [`epoch`], empty when deletes remove the row.
+    const FIELD_DELETED_AT: &'static str = "";
+    /// Stored columns in field order, to create and verify the table.
+    const COLUMNS: &'static [Column];
+
+    fn get_id(&self) -> i64;
+
+    fn insert(&self, db: impl Connect<DB>) -> impl Future<Output = Result<i64, CrudError>> + Send;
+    fn update(&self, db: impl Connect<DB>) -> impl Future<Output = Result<(), CrudError>> + Send;
+    fn delete(db: impl Connect<DB>, id: i64) -> impl Future<Output = Result<(), CrudError>> + Send;
+    fn fetch_one(
+        db: impl Connect<DB>,
+        id: i64,
+    ) -> impl Future<Output = Result<Self, CrudError>> + Send;
+
+    /// Applies the delete policy of each child type to the children of row `id`, on the
+    /// connection about to delete it. Types without children have nothing to do.
+    ///
+    /// With `trash` the row is only marked deleted: restricting children still block it, but
+    /// cascades and set nulls wait for the purge so a restore finds the children untouched.
+    /// Otherwise the row goes for good and trashed children count as well.
+    fn delete_children(
+        _conn: &mut DB::Connection,
+        _id: i64,
+        _trash: bool,
+    ) -> impl Future<Output = Result<(), CrudError>> + Send {
+        async { Ok(()) }
+    }
+
+    /// The deleted row `id`, the one `restore` and `purge` act on. Without `FIELD_DELETED_AT`
+    /// there is never one.
+    fn fetch_trashed(
+        _db: impl Connect<DB>,
+        _id: i64,
+    ) -> impl Future<Output = Result<Self, CrudError>> + Send {
+        async { Err(CrudError::NotFound) }
+    }
+
+    /// Brings a deleted row back. Without `FIELD_DELETED_AT` there is never one to restore.
+    fn restore(
+        _db: impl Connect<DB>,
+        _id: i64,
+    ) -> impl Future<Output = Result<(), CrudError>> + Send {
+        async { Err(CrudError::NotFound) }
+    }
+
+    /// Removes a deleted row for good. Without `FIELD_DELETED_AT` there is never one to purge.
+    fn purge(
+        _db: impl Connect<DB>,
+        _id: i64,
+    ) -> impl Future<Output = Result<(), CrudError>> + Send {
+        async { Err(CrudError::NotFound) }
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -16,11 +152,101 @@ pub enum QueryToken {
     Text(String),
     Numeric(i64),
     Float(f64),
+    Bool(bool),
+}
+
+/// Binds a `QueryToken` with its own type, so generated queries can pass `bind_token` to
+/// the `fill_query_*` helpers.
+pub trait BindToken {
+    fn bind_token(self, token: QueryToken) -> Self;
+}
+
+impl<'q> BindToken for Query<'q, Any, AnyArguments<'q>> {
+    fn bind_token(self, token: QueryToken) -> Self {
+        match token {
+            QueryToken::Text(value) => self.bind(value),
+            QueryToken::Numeric(value) => self.bind(value),
+            QueryToken::Float(value) => self.bind(value),
+            QueryToken::Bool(value) => self.bind(value),
+        }
+    }
+}
+
+impl<'q, O> BindToken for QueryAs<'q, Any, O, AnyArguments<'q>> {
+    fn bind_token(self, token: QueryToken) -> Self {
+        match token {
+            QueryToken::Text(value) => self.bind(value),
+            QueryToken::Numeric(value) => self.bind(value),
+            QueryToken::Float(value) => self.bind(value),
+            QueryToken::Bool(value) => self.bind(value),
+        }
+    }
+}
+
+impl<'q, O> BindToken for QueryScalar<'q, Any, O, AnyArguments<'q>> {
+    fn bind_token(self, token: QueryToken) -> Self {
+        match token {
+            QueryToken::Text(value) => self.bind(value),
+            QueryToken::Numeric(value) => self.bind(value),
+            QueryToken::Float(value) => self.bind(value),
+            QueryToken::Bool(value) => self.bind(value),
+        }
+    }
+}
+
+/// A column `?fields=` can ask for, with how to read it from a row.
+#[derive(Debug, Clone, Copy)]
+pub struct SelectField {
+    pub name: &'static str,
+    pub decode: fn(&AnyRow, &str) -> Result<Value, CrudError>,
+}
+
+/// Reads column `name` as the field type and serializes it, so a sparse row has the same
+/// JSON values as the whole one.
+pub fn decode_field<T>(row: &AnyRow, name: &str) -> Result<Value, CrudError>
+where
+    T: for<'r> sqlx::Decode<'r, Any> + sqlx::Type<Any> + Serialize,
+{
+    use sqlx::Row;
+
+    serde_json::to_value(row.try_get::<T, _>(name)?).map_err(|e| CrudError::Database(e.to_string()))
+}
+
+pub fn select_row(row: &AnyRow, fields: &[SelectField]) -> Result<Map<String, Value>, CrudError> {
+    fields
+        .iter()
+        .map(|field| Ok((field.name.to_string(), (field.decode)(row, field.name)?)))
+        .collect()
+}
+
+/// Which rows of a soft deleted type a list request sees.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trashed {
+    #[default]
+    Without,
+    With,
+    Only,
+}
+
+/// What a list request narrows, orders and pages the rows by.
+#[derive(Debug, Clone, Default)]
+pub struct ListQuery {
+    pub search: Vec<SearchTerm>,
+    pub trashed: Trashed,
+    pub filters: Vec<Filter>,
+    pub order: Vec<OrderKey>,
+    pub parent_id: Option<i64>,
+    /// Values of the `order` keys of the last row seen by a keyset scan.
+    pub after: Vec<QueryToken>,
+    pub offset: i64,
+    pub limit: i64,
 }
 
 pub trait DatabaseFetchAll<DB>
 where
-    Self: Sized,
+    Self: Sized + Database<DB>,
+    DB: sqlx::Database,
 {
     const FIELD_PARENT: &'static str = "";
 
@@ -28,60 +254,130 @@ where
     const FIELDS_NUMERIC: &'static [&'static str] = &[];
     const FIELDS_FLOAT: &'static [&'static str] = &[];
 
-    fn get_field_array(token: &QueryToken) -> &'static [&'static str] {
-        match token {
-            QueryToken::Text(_) => Self::FIELDS_TEXT,
-            QueryToken::Numeric(_) => Self::FIELDS_NUMERIC,
-            QueryToken::Float(_) => Self::FIELDS_FLOAT,
+    /// FTS5 table indexing the text columns, with the row id as its rowid. On SQLite, search
+    /// terms without a field are looked up in it instead of going through `LIKE`. Nothing
+    /// here writes to it, the triggers `Table::create` emits along with it keep it current.
+    const FULL_TEXT_TABLE: &'static str = "";
+    /// `tsvector` column of the table doing the same on Postgres, generated from the text
+    /// columns with [`TEXT_SEARCH_CONFIG`] as `Table::create` emits it.
+    const FULL_TEXT_COLUMN: &'static str = "";
+
+    fn search(query: &str) -> Result<Vec<SearchTerm>, CrudError> {
+        let search = SearchTerm::parse(
+            SearchFields {
+                text: Self::FIELDS_TEXT,
+                numeric: Self::FIELDS_NUMERIC,
+                float: Self::FIELDS_FLOAT,
+            },
+            query,
+        )?;
+
+        let full_text = !Self::FULL_TEXT_TABLE.is_empty() || !Self::FULL_TEXT_COLUMN.is_empty();
+        let mut unscoped = search.iter().filter(|term| !term.scoped).peekable();
+
+        if full_text && unscoped.peek().is_some() && unscoped.all(|term| term.negated) {
+            return Err(CrudError::InvalidQuery(
+                "Full-text search needs a term that is not negated".to_string(),
+            ));
         }
+
+        Ok(search)
     }
 
-    fn tokens(query: String) -> Vec<QueryToken> {
-        let tokens = query.split_whitespace().map(|s| s.trim().to_string());
+    /// Full-text query for `search`, `None` when `dialect` has no index configured or there
+    /// is nothing to look up in it.
+    fn full_text(search: &[SearchTerm], dialect: Dialect) -> Option<String> {
+        let enabled = match dialect {
+            Dialect::Sqlite => !Self::FULL_TEXT_TABLE.is_empty(),
+            Dialect::Postgres => !Self::FULL_TEXT_COLUMN.is_empty(),
+            Dialect::MySql => false,
+        };
 
-        let iter = tokens
-            .clone()
-            .take_while(|_| !Self::FIELDS_TEXT.is_empty())
-            .map(|token| QueryToken::Text(format!("%{}%", token.trim().to_string())));
+        enabled
+            .then(|| SearchTerm::full_text(search, dialect))
+            .flatten()
+    }
+
+    fn create_query_full_text(placeholders: &mut Placeholders) -> String {
+        match placeholders.dialect() {
+            Dialect::Postgres => format!(
+                "{} @@ websearch_to_tsquery('{TEXT_SEARCH_CONFIG}', {})",
+                Self::FULL_TEXT_COLUMN,
+                placeholders.bind()
+            ),
+            _ => format!(
+                "{} IN (SELECT rowid FROM {} WHERE {} MATCH {})",
+                Self::FIELD_ID,
+                Self::FULL_TEXT_TABLE,
+                Self::FULL_TEXT_TABLE,
+                placeholders.bind()
+            ),
+        }
+    }
 
-        let iter = tokens
-            .clone()
-            .take_while(|_| !Self::FIELDS_NUMERIC.is_empty())
-            .filter_map(|token| Some(QueryToken::Numeric(token.parse::<i64>().ok()?)))
-            .chain(iter);
+    /// Relevance of a row for the full-text query, lower is better like FTS5's `rank`.
+    fn create_query_rank(placeholders: &mut Placeholders) -> String {
+        match placeholders.dialect() {
+            Dialect::Postgres => format!(
+                "-ts_rank({}, websearch_to_tsquery('{TEXT_SEARCH_CONFIG}', {}))",
+                Self::FULL_TEXT_COLUMN,
+                placeholders.bind()
+            ),
+            _ => format!(
+                "(SELECT rank FROM {} WHERE {} MATCH {} AND rowid = {}.{})",
+                Self::FULL_TEXT_TABLE,
+                Self::FULL_TEXT_TABLE,
+                placeholders.bind(),
+                Self::TABLE,
+                Self::FIELD_ID
+            ),
+        }
+    }
 
-        let iter = tokens
-            .take_while(|_| !Self::FIELDS_FLOAT.is_empty())
-            .filter_map(|token| Some(QueryToken::Float(token.parse::<f64>().ok()?)))
-            .chain(iter);
+    const FIELDS_FILTER: &'static [FilterField] = &[];
 
-        iter.collect::<Vec<_>>()
+    fn filters(pairs: &[(String, String)]) -> Result<Vec<Filter>, CrudError> {
+        Filter::parse(Self::FIELDS_FILTER, pairs)
     }
 
-    fn create_query_where(tokens: &Vec<QueryToken>) -> Option<String> {
+    fn create_query_where(
+        search: &[SearchTerm],
+        filters: &[Filter],
+        trashed: Trashed,
+        keyset: Option<(&[OrderKey], &[QueryToken])>,
+        placeholders: &mut Placeholders,
+    ) -> Option<String> {
         let mut pieces = vec![];
 
         if !Self::FIELD_PARENT.is_empty() {
-            pieces.push(format!("{} = ?", Self::FIELD_PARENT));
+            pieces.push(format!("{} = {}", Self::FIELD_PARENT, placeholders.bind()));
         }
 
-        if !tokens.is_empty() {
-            pieces.push(format!(
-                "({})",
-                tokens
-                    .iter()
-                    .flat_map(|token| {
-                        Self::get_field_array(token).iter().map(move |field| {
-                            if let QueryToken::Text(_) = token {
-                                format!("{field} LIKE ?")
-                            } else {
-                                format!("{field} = ?")
-                            }
-                        })
-                    })
-                    .collect::<Vec<_>>()
-                    .join(" OR ")
-            ));
+        if !Self::FIELD_DELETED_AT.is_empty() {
+            match trashed {
+                Trashed::Without => pieces.push(format!("{} IS NULL", Self::FIELD_DELETED_AT)),
+                Trashed::Only => pieces.push(format!("{} IS NOT NULL", Self::FIELD_DELETED_AT)),
+                Trashed::With => {}
+            }
+        }
+
+        let full_text = Self::full_text(search, placeholders.dialect()).is_some();
+        if full_text {
+            pieces.push(Self::create_query_full_text(placeholders));
+        }
+
+        pieces.extend(
+            search
+                .iter()
+                .filter(|term| !full_text || term.scoped)
+                .map(|term| term.sql(placeholders)),
+        );
+        pieces.extend(filters.iter().map(|filter| filter.sql(placeholders)));
+
+        if let Some((order, after)) =
+            keyset.filter(|(order, after)| !after.is_empty() && order.len() == after.len())
+        {
+            pieces.push(Self::create_query_keyset(order, after, placeholders));
         }
 
         if !pieces.is_empty() {
@@ -91,12 +387,79 @@ where
         }
     }
 
-    fn fill_query_where<Q, F>(tokens: Vec<QueryToken>, mut query: Q, mut f: F) -> Q
+    fn fill_query_where<Q, F>(search: &[SearchTerm], dialect: Dialect, mut query: Q, mut f: F) -> Q
+    where
+        F: FnMut(Q, QueryToken) -> Q,
+    {
+        let full_text = Self::full_text(search, dialect);
+        let scoped = full_text.is_some();
+
+        if let Some(full_text) = full_text {
+            query = f(query, QueryToken::Text(full_text));
+        }
+
+        for (_, token) in search
+            .iter()
+            .filter(|term| !scoped || term.scoped)
+            .flat_map(|term| term.matches.iter())
+        {
+            query = f(query, token.clone());
+        }
+
+        query
+    }
+
+    fn fill_query_filter<Q, F>(filters: &[Filter], mut query: Q, mut f: F) -> Q
+    where
+        F: FnMut(Q, QueryToken) -> Q,
+    {
+        for token in filters.iter().flat_map(|filter| filter.values.iter()) {
+            query = f(query, token.clone());
+        }
+
+        query
+    }
+
+    /// Rows after `after` in `order`, spelled out key by key since the directions can differ:
+    /// `(a > ?) OR (a = ? AND b < ?) OR ...`. Order keys are expected to be `NOT NULL`.
+    fn create_query_keyset(
+        order: &[OrderKey],
+        after: &[QueryToken],
+        placeholders: &mut Placeholders,
+    ) -> String {
+        let alternatives = (0..after.len())
+            .map(|i| {
+                let mut terms = order[..i]
+                    .iter()
+                    .map(|key| format!("{} = {}", key.field, placeholders.bind()))
+                    .collect::<Vec<_>>();
+
+                let key = &order[i];
+                let operator = if key.desc { "<" } else { ">" };
+                terms.push(format!("{} {operator} {}", key.field, placeholders.bind()));
+
+                format!("({})", terms.join(" AND "))
+            })
+            .collect::<Vec<_>>();
+
+        format!("({})", alternatives.join(" OR "))
+    }
+
+    fn fill_query_keyset<Q, F>(
+        order: &[OrderKey],
+        after: &[QueryToken],
+        mut query: Q,
+        mut f: F,
+    ) -> Q
     where
         F: FnMut(Q, QueryToken) -> Q,
     {
-        for token in tokens {
-            for _ in 0..Self::get_field_array(&token).len() {
+        if order.len() != after.len() {
+            return query;
+        }
+
+        for i in 0..after.len() {
+            for token in &after[..=i] {
                 query = f(query, token.clone());
             }
         }
@@ -106,32 +469,274 @@ where
 
     const FIELDS_ORDER: &'static [&'static str] = &[];
 
-    fn create_query_order(order: String) -> Option<String> {
-        if Self::FIELDS_ORDER.contains(&order.as_str()) {
-            Some(format!("ORDER BY {order}"))
-        } else {
-            None
+    /// Order used when a request has none, in the `order` parameter syntax.
+    const DEFAULT_ORDER: &'static str = "";
+
+    /// Parses an `order` parameter, or `DEFAULT_ORDER` when empty, and appends the id as a
+    /// tie-breaker unless already there, so rows always come back in the same order.
+    fn order(order: Option<&str>) -> Result<Vec<OrderKey>, CrudError> {
+        let order = order
+            .filter(|order| !order.is_empty())
+            .unwrap_or(Self::DEFAULT_ORDER);
+
+        let mut keys = match order {
+            "" => vec![],
+            _ => OrderKey::parse(Self::FIELDS_ORDER, order)?,
+        };
+
+        if Self::FULL_TEXT_TABLE.is_empty()
+            && Self::FULL_TEXT_COLUMN.is_empty()
+            && keys.iter().any(|key| key.field == RANK)
+        {
+            return Err(CrudError::InvalidQuery(format!(
+                "Ordering by `{RANK}` is not allowed"
+            )));
+        }
+
+        if !keys.iter().any(|key| key.field == Self::FIELD_ID) {
+            keys.push(OrderKey::asc(Self::FIELD_ID));
+        }
+
+        Ok(keys)
+    }
+
+    /// `_rank` keys are left out when there is no full-text query to rank by.
+    fn create_query_order(
+        order: &[OrderKey],
+        search: &[SearchTerm],
+        placeholders: &mut Placeholders,
+    ) -> Option<String> {
+        let dialect = placeholders.dialect();
+        let rank = Self::full_text(search, dialect).is_some();
+
+        let keys = order
+            .iter()
+            .filter(|key| rank || key.field != RANK)
+            .map(|key| match key.field {
+                RANK if key.desc => format!("{} DESC", Self::create_query_rank(placeholders)),
+                RANK => Self::create_query_rank(placeholders),
+                _ => key.sql(dialect),
+            })
+            .collect::<Vec<_>>();
+
+        if keys.is_empty() {
+            return None;
         }
+
+        Some(format!("ORDER BY {}", keys.join(", ")))
     }
 
-    async fn fetch_all(
-        pool: &DB,
-        search: Option<String>,
-        order: Option<String>,
-        parent_id: Option<i64>,
-        offset: i64,
-        limit: i64,
-    ) -> Result<Vec<Self>, impl Error>;
+    fn fill_query_order<Q, F>(
+        order: &[OrderKey],
+        search: &[SearchTerm],
+        dialect: Dialect,
+        mut query: Q,
+        mut f: F,
+    ) -> Q
+    where
+        F: FnMut(Q, QueryToken) -> Q,
+    {
+        if let Some(full_text) = Self::full_text(search, dialect) {
+            for _ in order.iter().filter(|key| key.field == RANK) {
+                query = f(query, QueryToken::Text(full_text.clone()));
+            }
+        }
+
+        query
+    }
+
+    fn create_query_pagination(placeholders: &mut Placeholders) -> String {
+        format!(
+            "LIMIT {} OFFSET {}",
+            placeholders.bind(),
+            placeholders.bind()
+        )
+    }
+
+    /// Everything of a list query after the projection, bound by `fill_query_list`.
+    fn create_query_list(list: &ListQuery, placeholders: &mut Placeholders) -> String {
+        let keyset = Some((list.order.as_slice(), list.after.as_slice()));
+        let sql_where = Self::create_query_where(
+            &list.search,
+            &list.filters,
+            list.trashed,
+            keyset,
+            placeholders,
+        );
+        let sql_order = Self::create_query_order(&list.order, &list.search, placeholders);
+        let sql_pagination = Self::create_query_pagination(placeholders);
+
+        format!(
+            "FROM {} {} {} {}",
+            Self::TABLE,
+            sql_where.unwrap_or_default(),
+            sql_order.unwrap_or_default(),
+            sql_pagination
+        )
+    }
+
+    fn fill_query_list<Q, F>(list: &ListQuery, dialect: Dialect, mut query: Q, mut f: F) -> Q
+    where
+        F: FnMut(Q, QueryToken) -> Q,
+    {
+        if !Self::FIELD_PARENT.is_empty() {
+            query = f(
+                query,
+                QueryToken::Numeric(list.parent_id.unwrap_or_default()),
+            );
+        }
+
+        query = Self::fill_query_where(&list.search, dialect, query, &mut f);
+        query = Self::fill_query_filter(&list.filters, query, &mut f);
+        query = Self::fill_query_keyset(&list.order, &list.after, query, &mut f);
+        query = Self::fill_query_order(&list.order, &list.search, dialect, query, &mut f);
+
+        query = f(query, QueryToken::Numeric(list.limit));
+        f(query, QueryToken::Numeric(list.offset))
+    }
+
+    const FIELDS_SELECT: &'static [SelectField] = &[];
+
+    /// Parses a comma separated `fields` parameter against `FIELDS_SELECT`.
+    fn select(fields: &str) -> Result<Vec<SelectField>, CrudError> {
+        fields
+            .split(',')
+            .map(str::trim)
+            .map(|name| {
+                Self::FIELDS_SELECT
+                    .iter()
+                    .find(|field| field.name == name)
+                    .copied()
+                    .ok_or_else(|| {
+                        CrudError::InvalidQuery(format!("Field `{name}` cannot be selected"))
+                    })
+            })
+            .collect()
+    }
+
+    /// Query after `SELECT *` for the rows whose `field` is one of `count` ids, at most
+    /// `limit` of them per value of `field`.
+    fn create_query_in(
+        field: &str,
+        count: usize,
+        limit: Option<i64>,
+        trashed: Trashed,
+        placeholders: &mut Placeholders,
+    ) -> String {
+        let ids = (0..count)
+            .map(|_| placeholders.bind())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut sql_where = format!("{field} IN ({ids})");
+        if !Self::FIELD_DELETED_AT.is_empty() {
+            match trashed {
+                Trashed::Without => {
+                    sql_where += &format!(" AND {} IS NULL", Self::FIELD_DELETED_AT)
+                }
+                Trashed::Only => {
+                    sql_where += &format!(" AND {} IS NOT NULL", Self::FIELD_DELETED_AT)
+                }
+                Trashed::With => {}
+            }
+        }
+
+        match limit {
+            Some(limit) => format!(
+                "FROM (SELECT *, ROW_NUMBER() OVER (PARTITION BY {field} ORDER BY {id}) AS _row FROM {table} WHERE {sql_where}) {table} WHERE _row <= {limit} ORDER BY {field}, {id}",
+                table = Self::TABLE,
+                id = Self::FIELD_ID,
+            ),
+            None => format!(
+                "FROM {} WHERE {sql_where} ORDER BY {}",
+                Self::TABLE,
+                Self::FIELD_ID
+            ),
+        }
+    }
+
+    fn fetch_all(
+        db: impl Connect<DB>,
+        list: &ListQuery,
+    ) -> impl Future<Output = Result<Vec<Self>, CrudError>> + Send;
+
+    /// Rows whose `field` is one of `ids` in a single query, see `create_query_in`.
+    fn fetch_in(
+        db: impl Connect<DB>,
+        field: &str,
+        ids: &[i64],
+        limit: Option<i64>,
+        trashed: Trashed,
+    ) -> impl Future<Output = Result<Vec<Self>, CrudError>> + Send;
+
+    /// `fetch_all` reading only `fields` into JSON objects.
+    fn fetch_all_fields(
+        db: impl Connect<DB>,
+        list: &ListQuery,
+        fields: &[SelectField],
+    ) -> impl Future<Output = Result<Vec<Map<String, Value>>, CrudError>> + Send;
+
+    /// `fetch_one` reading only `fields` into a JSON object.
+    fn fetch_one_fields(
+        db: impl Connect<DB>,
+        id: i64,
+        fields: &[SelectField],
+    ) -> impl Future<Output = Result<Map<String, Value>, CrudError>> + Send;
+
+    /// Number of rows `fetch_all` would return without the cursor and pagination.
+    fn count_all(
+        db: impl Connect<DB>,
+        list: &ListQuery,
+    ) -> impl Future<Output = Result<i64, CrudError>> + Send;
 }
 
-pub trait MatchParent<DB> {
+pub trait MatchParent<DB>
+where
+    DB: sqlx::Database,
+{
     type Parent;
 
-    async fn fetch_parent(pool: &DB, parent_id: i64, id: i64) -> Result<Self::Parent, impl Error>;
+    fn fetch_parent(
+        db: impl Connect<DB>,
+        parent_id: i64,
+        id: i64,
+    ) -> impl Future<Output = Result<Self::Parent, CrudError>> + Send;
 
     fn get_parent_id(&mut self) -> i64;
 }
 
+/// Related resources `?include=` can embed in a response. Derived from the `parent` field
+/// and the `children(...)` attribute of `#[derive(Crud)]`.
+pub trait Include<DB>
+where
+    DB: sqlx::Database,
+{
+    const INCLUDES: &'static [IncludeField] = &[];
+
+    fn includes(include: &str) -> Result<Vec<IncludeField>, CrudError> {
+        include
+            .split(',')
+            .map(str::trim)
+            .map(|name| {
+                Self::INCLUDES
+                    .iter()
+                    .find(|include| include.name == name)
+                    .copied()
+                    .ok_or_else(|| CrudError::InvalidQuery(format!("`{name}` cannot be included")))
+            })
+            .collect()
+    }
+
+    /// Embeds the resource `name` into each of `rows`, with one query for all of them.
+    fn include(
+        _db: impl Connect<DB>,
+        _name: &str,
+        _rows: &mut [Map<String, Value>],
+    ) -> impl Future<Output = Result<(), CrudError>> + Send {
+        async { Ok(()) }
+    }
+}
+
 pub trait Check
 where
     Self: Sized,
@@ -153,47 +758,272 @@ where
 mod tests {
     use std::vec;
 
-    use crate::router::Pool;
+    use crate::router::Db;
 
     use super::*;
 
     struct QueryStruct;
-    impl DatabaseFetchAll<Pool> for QueryStruct {
+    impl Database<Db> for QueryStruct {
+        const TABLE: &'static str = "query";
+        const FIELD_ID: &'static str = "id";
+        const COLUMNS: &'static [Column] = &[];
+
+        fn get_id(&self) -> i64 {
+            0
+        }
+
+        async fn insert(&self, _db: impl Connect<Db>) -> Result<i64, CrudError> {
+            Ok(0)
+        }
+
+        async fn update(&self, _db: impl Connect<Db>) -> Result<(), CrudError> {
+            Ok(())
+        }
+
+        async fn delete(_db: impl Connect<Db>, _id: i64) -> Result<(), CrudError> {
+            Ok(())
+        }
+
+        async fn fetch_one(_db: impl Connect<Db>, _id: i64) -> Result<Self, CrudError> {
+            Err(CrudError::NotFound)
+        }
+    }
+
+    impl DatabaseFetchAll<Db> for QueryStruct {
         const FIELDS_TEXT: &'static [&'static str] = &["title", "name"];
         const FIELDS_NUMERIC: &'static [&'static str] = &["id", "size"];
         const FIELDS_FLOAT: &'static [&'static str] = &["lat", "lon"];
+        const FIELDS_ORDER: &'static [&'static str] = &["id", "title"];
+        const DEFAULT_ORDER: &'static str = "title";
+        const FIELDS_FILTER: &'static [FilterField] = &[FilterField {
+            name: "size",
+            parse: i64::parse,
+            ops: &[FilterOp::Gte, FilterOp::Lt],
+        }];
 
         async fn fetch_all(
-            _pool: &Pool,
-            _search: Option<String>,
-            _order: Option<String>,
-            _parent_id: Option<i64>,
-            _offset: i64,
-            _limit: i64,
-        ) -> Result<Vec<Self>, impl Error> {
-            Ok::<Vec<Self>, std::io::Error>(vec![])
+            _db: impl Connect<Db>,
+            _list: &ListQuery,
+        ) -> Result<Vec<Self>, CrudError> {
+            Ok(vec![])
+        }
+
+        async fn fetch_in(
+            _db: impl Connect<Db>,
+            _field: &str,
+            _ids: &[i64],
+            _limit: Option<i64>,
+            _trashed: Trashed,
+        ) -> Result<Vec<Self>, CrudError> {
+            Ok(vec![])
+        }
+
+        async fn fetch_all_fields(
+            _db: impl Connect<Db>,
+            _list: &ListQuery,
+            _fields: &[SelectField],
+        ) -> Result<Vec<Map<String, Value>>, CrudError> {
+            Ok(vec![])
+        }
+
+        async fn fetch_one_fields(
+            _db: impl Connect<Db>,
+            _id: i64,
+            _fields: &[SelectField],
+        ) -> Result<Map<String, Value>, CrudError> {
+            Err(CrudError::NotFound)
+        }
+
+        async fn count_all(_db: impl Connect<Db>, _list: &ListQuery) -> Result<i64, CrudError> {
+            Ok(0)
         }
     }
 
     #[test]
-    fn query_tokens() {
-        let mut tokens = QueryStruct::tokens("name 1 1.23".to_string()).into_iter();
+    fn query_search() {
+        let search = QueryStruct::search("name -1.23").unwrap();
 
-        assert_eq!(tokens.next(), Some(QueryToken::Float(1.0)));
-        assert_eq!(tokens.next(), Some(QueryToken::Float(1.23)));
-        assert_eq!(tokens.next(), Some(QueryToken::Numeric(1)));
-        assert_eq!(tokens.next(), Some(QueryToken::Text("%name%".to_string())));
-        assert_eq!(tokens.next(), Some(QueryToken::Text("%1%".to_string())));
-        assert_eq!(tokens.next(), Some(QueryToken::Text("%1.23%".to_string())));
-        assert_eq!(tokens.next(), None);
+        assert_eq!(search.len(), 2);
+        assert_eq!(
+            search[0].matches,
+            [
+                ("title", QueryToken::Text("%name%".to_string())),
+                ("name", QueryToken::Text("%name%".to_string())),
+            ]
+        );
+        assert!(search[1].negated);
+        assert_eq!(
+            search[1]
+                .matches
+                .iter()
+                .map(|(f, _)| *f)
+                .collect::<Vec<_>>(),
+            ["lat", "lon", "title", "name"]
+        );
+        assert!(QueryStruct::search("other:1").is_err());
     }
 
     #[test]
     fn query_create_where() {
-        let tokens = QueryStruct::tokens("name 1 1.23".to_string());
+        let search = QueryStruct::search("name 1 1.23").unwrap();
+
+        let sql = QueryStruct::create_query_where(
+            &search,
+            &[],
+            Trashed::Without,
+            None,
+            &mut Dialect::Sqlite.placeholders(),
+        );
+
+        assert_eq!(sql, Some("WHERE (title LIKE ? OR name LIKE ?) AND (lat = ? OR lon = ? OR id = ? OR size = ? OR title LIKE ? OR name LIKE ?) AND (lat = ? OR lon = ? OR title LIKE ? OR name LIKE ?)".to_string()))
+    }
+
+    #[test]
+    fn query_create_where_postgres() {
+        let search = QueryStruct::search("name -1").unwrap();
+
+        let sql = QueryStruct::create_query_where(
+            &search,
+            &[],
+            Trashed::Without,
+            None,
+            &mut Dialect::Postgres.placeholders(),
+        );
+
+        assert_eq!(
+            sql,
+            Some("WHERE (title LIKE $1 OR name LIKE $2) AND NOT (lat = $3 OR lon = $4 OR id = $5 OR size = $6 OR title LIKE $7 OR name LIKE $8)".to_string())
+        )
+    }
+
+    #[test]
+    fn query_create_pagination() {
+        let mut placeholders = Dialect::Postgres.placeholders();
+        let search = QueryStruct::search("name").unwrap();
+
+        QueryStruct::create_query_where(&search, &[], Trashed::Without, None, &mut placeholders);
+
+        assert_eq!(
+            QueryStruct::create_query_pagination(&mut placeholders),
+            "LIMIT $3 OFFSET $4"
+        );
+        assert_eq!(
+            QueryStruct::create_query_pagination(&mut Dialect::MySql.placeholders()),
+            "LIMIT ? OFFSET ?"
+        );
+    }
+
+    #[test]
+    fn query_create_in() {
+        assert_eq!(
+            QueryStruct::create_query_in(
+                "id",
+                2,
+                None,
+                Trashed::Without,
+                &mut Dialect::Sqlite.placeholders()
+            ),
+            "FROM query WHERE id IN (?, ?) ORDER BY id"
+        );
+        assert_eq!(
+            QueryStruct::create_query_in(
+                "parent",
+                3,
+                Some(5),
+                Trashed::With,
+                &mut Dialect::Postgres.placeholders()
+            ),
+            "FROM (SELECT *, ROW_NUMBER() OVER (PARTITION BY parent ORDER BY id) AS _row FROM query WHERE parent IN ($1, $2, $3)) query WHERE _row <= 5 ORDER BY parent, id"
+        );
+    }
+
+    #[test]
+    fn query_create_where_keyset() {
+        let search = QueryStruct::search("name").unwrap();
+        let order = QueryStruct::order(Some("-title")).unwrap();
+        let after = [QueryToken::Text("a".to_string()), QueryToken::Numeric(1)];
+
+        let sql = QueryStruct::create_query_where(
+            &search,
+            &[],
+            Trashed::Without,
+            Some((&order, &after)),
+            &mut Dialect::Postgres.placeholders(),
+        );
+
+        assert_eq!(
+            sql,
+            Some("WHERE (title LIKE $1 OR name LIKE $2) AND ((title < $3) OR (title = $4 AND id > $5))".to_string())
+        );
+
+        let mut bound = vec![];
+        QueryStruct::fill_query_keyset(&order, &after, (), |_, token| bound.push(token));
+        assert_eq!(
+            bound,
+            [after[0].clone(), after[0].clone(), after[1].clone()]
+        );
+
+        assert_eq!(
+            QueryStruct::create_query_where(
+                &[],
+                &[],
+                Trashed::Without,
+                Some((&order, &[])),
+                &mut Dialect::Sqlite.placeholders()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn query_order() {
+        let order = QueryStruct::order(Some("title:desc:nulls_first")).unwrap();
+
+        assert_eq!(
+            QueryStruct::create_query_order(&order, &[], &mut Dialect::Postgres.placeholders()),
+            Some("ORDER BY title DESC NULLS FIRST, id".to_string())
+        );
+        assert_eq!(
+            QueryStruct::create_query_order(
+                &QueryStruct::order(Some("-id,title")).unwrap(),
+                &[],
+                &mut Dialect::Sqlite.placeholders()
+            ),
+            Some("ORDER BY id DESC, title".to_string())
+        );
+        assert_eq!(
+            QueryStruct::create_query_order(
+                &QueryStruct::order(None).unwrap(),
+                &[],
+                &mut Dialect::Sqlite.placeholders()
+            ),
+            Some("ORDER BY title, id".to_string())
+        );
+        assert!(QueryStruct::order(Some("lat")).is_err());
+        assert!(QueryStruct::order(Some("_rank")).is_err());
+    }
+
+    #[test]
+    fn query_create_where_filter() {
+        let search = QueryStruct::search("name").unwrap();
+        let filters = QueryStruct::filters(&[
+            ("filter[size][gte]".to_string(), "10".to_string()),
+            ("filter[size][lt]".to_string(), "20".to_string()),
+        ])
+        .unwrap();
 
-        let sql = QueryStruct::create_query_where(&tokens);
+        let sql = QueryStruct::create_query_where(
+            &search,
+            &filters,
+            Trashed::Without,
+            None,
+            &mut Dialect::Postgres.placeholders(),
+        );
 
-        assert_eq!(sql, Some("WHERE (lat = ? OR lon = ? OR lat = ? OR lon = ? OR id = ? OR size = ? OR title LIKE ? OR name LIKE ? OR title LIKE ? OR name LIKE ? OR title LIKE ? OR name LIKE ?)".to_string()))
+        assert_eq!(
+            sql,
+            Some("WHERE (title LIKE $1 OR name LIKE $2) AND size >= $3 AND size < $4".to_string())
+        );
+        assert!(QueryStruct::filters(&[("filter[lat]".to_string(), "1".to_string())]).is_err());
     }
 }
@@ -1,23 +1,537 @@
 use axum::{
-    routing::{delete, get, post, put},
-    Router,
+    routing::{get, MethodRouter},
+    Extension, Router,
 };
+use serde::{de::DeserializeOwned, Serialize};
+use validator::Validate;
 
-use crate::{crud, list, types::dummy::Dummy};
+use crate::{
+    bulk,
+    config::{Config, PagingConfig},
+    crud, health, list,
+    prelude::*,
+    schema::Table,
+    types::{dummy::Dummy, sub_dummy::SubDummy},
+};
 
-pub type SqlxPool = sqlx::pool::Pool<sqlx::Any>;
+pub type Db = sqlx::Any;
+pub type SqlxPool = sqlx::pool::Pool<Db>;
 pub type Pool = SqlxPool;
 
-pub fn router() -> axum::Router<Pool> {
-    Router::new()
+pub fn router(config: &Config) -> CrudRouter {
+    CrudRouter::new()
+        .paging(config.paging.clone())
+        .operations(Operations {
+            bulk: config.features.bulk,
+            trash: config.features.trash,
+            ..Default::default()
+        })
         .route("/", get(root))
-        .route("/dummy/", get(list::list::<Dummy>))
-        .route("/dummy/", post(crud::create::<Dummy>))
-        .route("/dummy/:id", get(crud::retrieve::<Dummy>))
-        .route("/dummy/:id", put(crud::update::<Dummy>))
-        .route("/dummy/:id", delete(crud::delete::<Dummy>))
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
+        .route("/health", get(health::health))
+        .resource::<Dummy>("/dummy")
+        .nested::<SubDummy>("/dummy/:id/subdummy")
 }
 
 async fn root() -> &'static str {
     "It works!"
 }
+
+/// Operations registered for a resource, all enabled by default.
+#[derive(Debug, Clone, Copy)]
+pub struct Operations {
+    pub list: bool,
+    pub create: bool,
+    pub retrieve: bool,
+    pub update: bool,
+    pub patch: bool,
+    pub delete: bool,
+    /// Only used by top level resources, nested ones have no bulk endpoint.
+    pub bulk: bool,
+    /// `/:id/restore` and `/:id/purge`, only for top level resources with a soft delete
+    /// column.
+    pub trash: bool,
+}
+
+impl Default for Operations {
+    fn default() -> Self {
+        Self {
+            list: true,
+            create: true,
+            retrieve: true,
+            update: true,
+            patch: true,
+            delete: true,
+            bulk: true,
+            trash: true,
+        }
+    }
+}
+
+/// Registers the list and CRUD routes of a resource in one call.
+///
+/// `resource::<T>("/x")` serves `/x/` (list, create), `/x/bulk`, `/x/:id` and, for soft
+/// deleted types, `/x/:id/restore` and `/x/:id/purge`.
+/// `nested::<T>("/x/:id/y")` serves `/x/:id/y/` and `/x/:id/y/:<T::FIELD_ID>`, so the
+/// parent and the item parameters never share a name.
+///
+/// The tables of the registered resources are kept for the schema check.
+#[derive(Default)]
+pub struct CrudRouter {
+    router: Router<Pool>,
+    tables: Vec<Table>,
+    paging: PagingConfig,
+    operations: Operations,
+}
+
+impl CrudRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Page sizes of the list endpoints registered from now on.
+    pub fn paging(mut self, paging: PagingConfig) -> Self {
+        self.paging = paging;
+        self
+    }
+
+    /// Operations of the resources registered from now on by `resource` and `nested`.
+    pub fn operations(mut self, operations: Operations) -> Self {
+        self.operations = operations;
+        self
+    }
+
+    pub fn route(mut self, path: &str, method_router: MethodRouter<Pool>) -> Self {
+        self.router = self.router.route(path, method_router);
+        self
+    }
+
+    pub fn resource<T>(self, path: &str) -> Self
+    where
+        T: Database<Db>
+            + DatabaseFetchAll<Db>
+            + Include<Db>
+            + Validate
+            + Check
+            + Serialize
+            + DeserializeOwned
+            + Send
+            + Sync
+            + 'static,
+    {
+        let operations = self.operations;
+        self.resource_with::<T>(path, operations)
+    }
+
+    pub fn resource_with<T>(mut self, path: &str, operations: Operations) -> Self
+    where
+        T: Database<Db>
+            + DatabaseFetchAll<Db>
+            + Include<Db>
+            + Validate
+            + Check
+            + Serialize
+            + DeserializeOwned
+            + Send
+            + Sync
+            + 'static,
+    {
+        let path = path.trim_end_matches('/');
+        self.register::<T>();
+
+        let mut collection = MethodRouter::new();
+        if operations.list {
+            collection = collection.get(list::list::<T>);
+        }
+        if operations.create {
+            collection = collection.post(crud::create::<T>);
+        }
+        if operations.list || operations.create {
+            let limits = self.paging.limits(T::TABLE);
+            self.router = self
+                .router
+                .route(&format!("{path}/"), collection.layer(Extension(limits)));
+        }
+
+        // Each bulk method follows the single item operation it batches.
+        if operations.bulk && (operations.create || operations.update || operations.delete) {
+            let mut batch = MethodRouter::new();
+            if operations.create {
+                batch = batch.post(bulk::bulk_create::<T>);
+            }
+            if operations.update {
+                batch = batch.put(bulk::bulk_update::<T>);
+            }
+            if operations.delete {
+                batch = batch.delete(bulk::bulk_delete::<T>);
+            }
+            self.router = self.router.route(&format!("{path}/bulk"), batch);
+        }
+
+        if let Some(item) = item_routes(
+            operations,
+            crud::retrieve::<T>,
+            crud::update::<T>,
+            crud::patch::<T>,
+            crud::delete::<T>,
+        ) {
+            self.router = self.router.route(&format!("{path}/:id"), item);
+        }
+
+        if operations.trash && !T::FIELD_DELETED_AT.is_empty() {
+            self.router = self
+                .router
+                .route(
+                    &format!("{path}/:id/restore"),
+                    MethodRouter::new().post(crud::restore::<T>),
+                )
+                .route(
+                    &format!("{path}/:id/purge"),
+                    MethodRouter::new().delete(crud::purge::<T>),
+                );
+        }
+
+        self
+    }
+
+    pub fn nested<T>(self, path: &str) -> Self
+    where
+        T: Database<Db>
+            + DatabaseFetchAll<Db>
+            + Include<Db>
+            + MatchParent<Db>
+            + Validate
+            + Check
+            + Serialize
+            + DeserializeOwned
+            + Send
+            + Sync
+            + 'static,
+        T::Parent: Database<Db>,
+    {
+        let operations = self.operations;
+        self.nested_with::<T>(path, operations)
+    }
+
+    /// # Panics
+    ///
+    /// When `path` does not contain exactly one parameter, the parent id.
+    pub fn nested_with<T>(mut self, path: &str, operations: Operations) -> Self
+    where
+        T: Database<Db>
+            + DatabaseFetchAll<Db>
+            + Include<Db>
+            + MatchParent<Db>
+            + Validate
+            + Check
+            + Serialize
+            + DeserializeOwned
+            + Send
+            + Sync
+            + 'static,
+        T::Parent: Database<Db>,
+    {
+        let path = path.trim_end_matches('/');
+
+        let parameters = path.split('/').filter(|s| s.starts_with(':')).count();
+        assert!(
+            parameters == 1,
+            "nested path {path} must have exactly one parameter, found {parameters}"
+        );
+        self.register::<T>();
+
+        let mut collection = MethodRouter::new();
+        if operations.list {
+            collection = collection.get(list::sub_list::<T>);
+        }
+        if operations.create {
+            collection = collection.post(crud::sub_create::<T>);
+        }
+        if operations.list || operations.create {
+            let limits = self.paging.limits(T::TABLE);
+            self.router = self
+                .router
+                .route(&format!("{path}/"), collection.layer(Extension(limits)));
+        }
+
+        if let Some(item) = item_routes(
+            operations,
+            crud::sub_retrieve::<T>,
+            crud::sub_update::<T>,
+            crud::sub_patch::<T>,
+            crud::sub_delete::<T>,
+        ) {
+            self.router = self.router.route(&format!("{path}/:{}", T::FIELD_ID), item);
+        }
+
+        self
+    }
+
+    pub fn tables(&self) -> &[Table] {
+        &self.tables
+    }
+
+    pub fn into_router(self) -> Router<Pool> {
+        self.router
+    }
+
+    /// Records the table of `T`, once however many paths serve it.
+    fn register<T: Database<Db> + DatabaseFetchAll<Db>>(&mut self) {
+        if !self.tables.iter().any(|table| table.name == T::TABLE) {
+            self.tables.push(Table::of::<T>());
+        }
+    }
+}
+
+fn item_routes<R, U, P, D, TR, TU, TP, TD>(
+    operations: Operations,
+    retrieve: R,
+    update: U,
+    patch: P,
+    delete: D,
+) -> Option<MethodRouter<Pool>>
+where
+    R: axum::handler::Handler<TR, Pool>,
+    U: axum::handler::Handler<TU, Pool>,
+    P: axum::handler::Handler<TP, Pool>,
+    D: axum::handler::Handler<TD, Pool>,
+    TR: 'static,
+    TU: 'static,
+    TP: 'static,
+    TD: 'static,
+{
+    let mut item = MethodRouter::new();
+    if operations.retrieve {
+        item = item.get(retrieve);
+    }
+    if operations.update {
+        item = item.put(update);
+    }
+    if operations.patch {
+        item = item.patch(patch);
+    }
+    if operations.delete {
+        item = item.delete(delete);
+    }
+
+    (operations.retrieve || operations.update || operations.patch || operations.delete)
+        .then_some(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{self, Request, StatusCode};
+    use sqlx::any::AnyPoolOptions;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn database(size: i64) -> Pool {
+        sqlx::any::install_default_drivers();
+        let pool = AnyPoolOptions::new()
+            .max_connections(1) // needs to be 1, otherwise memory database is gone
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        crate::migrate::up(&pool).await.unwrap();
+
+        for i in 1..=size {
+            let _ = Dummy::insert(
+                &(Dummy {
+                    id_dummy: i,
+                    name: format!("name-{}", i),
+                    is_valid: Some(true),
+                }),
+                &pool,
+            )
+            .await;
+            let _ = SubDummy::insert(
+                &(SubDummy {
+                    id_sub_dummy: i,
+                    id_dummy: i,
+                    name: format!("name-{}", i),
+                    is_valid: Some(true),
+                }),
+                &pool,
+            )
+            .await;
+        }
+
+        pool
+    }
+
+    async fn status(app: Router, method: http::Method, uri: &str) -> StatusCode {
+        app.oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(String::new())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+    }
+
+    #[tokio::test]
+    async fn router_resources() {
+        let pool = database(1).await;
+
+        let app = router(&Config::default()).into_router().with_state(pool);
+
+        assert_eq!(
+            status(app.clone(), http::Method::GET, "/").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(app.clone(), http::Method::GET, "/dummy/").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(app.clone(), http::Method::GET, "/dummy/1").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(app.clone(), http::Method::GET, "/dummy/1/subdummy/").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(app.clone(), http::Method::GET, "/dummy/1/subdummy/1").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(app, http::Method::GET, "/dummy/2/subdummy/1").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn router_disabled_operations() {
+        let pool = database(1).await;
+
+        let app = CrudRouter::new()
+            .resource_with::<Dummy>(
+                "/dummy/",
+                Operations {
+                    create: false,
+                    update: false,
+                    patch: false,
+                    delete: false,
+                    bulk: false,
+                    ..Default::default()
+                },
+            )
+            .nested_with::<SubDummy>(
+                "/dummy/:id/subdummy",
+                Operations {
+                    list: false,
+                    create: false,
+                    ..Default::default()
+                },
+            )
+            .into_router()
+            .with_state(pool);
+
+        assert_eq!(
+            status(app.clone(), http::Method::GET, "/dummy/1").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(app.clone(), http::Method::DELETE, "/dummy/1").await,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            status(app.clone(), http::Method::POST, "/dummy/bulk").await,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            status(app.clone(), http::Method::GET, "/dummy/1/subdummy/").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(app, http::Method::GET, "/dummy/1/subdummy/1").await,
+            StatusCode::OK
+        );
+
+        let app = CrudRouter::new()
+            .resource_with::<Dummy>(
+                "/dummy/",
+                Operations {
+                    update: false,
+                    delete: false,
+                    ..Default::default()
+                },
+            )
+            .into_router()
+            .with_state(database(1).await);
+
+        assert_eq!(
+            status(app.clone(), http::Method::POST, "/dummy/bulk").await,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        assert_eq!(
+            status(app.clone(), http::Method::PUT, "/dummy/bulk").await,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            status(app, http::Method::DELETE, "/dummy/bulk").await,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+    }
+
+    #[test]
+    fn router_tables() {
+        let tables = CrudRouter::new()
+            .resource::<Dummy>("/dummy")
+            .resource::<Dummy>("/other")
+            .nested::<SubDummy>("/dummy/:id/subdummy")
+            .tables()
+            .iter()
+            .map(|table| table.name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(tables, ["dummy", "sub_dummy"]);
+    }
+
+    #[tokio::test]
+    async fn router_config() {
+        let pool = database(3).await;
+
+        let mut config = Config::default();
+        config.features.bulk = false;
+        config.paging.resources.insert(
+            "dummy".to_string(),
+            crate::config::ResourcePaging {
+                default_limit: Some(1),
+                max_limit: Some(2),
+            },
+        );
+
+        let app = router(&config).into_router().with_state(pool);
+
+        assert_eq!(
+            status(app.clone(), http::Method::GET, "/dummy/?limit=2").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(app.clone(), http::Method::GET, "/dummy/?limit=3").await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(app.clone(), http::Method::GET, "/dummy/1/subdummy/?limit=3").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(app, http::Method::POST, "/dummy/bulk").await,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn router_nested_without_parent() {
+        CrudRouter::new().nested::<SubDummy>("/subdummy");
+    }
+}
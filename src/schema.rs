@@ -0,0 +1,477 @@
+use sqlx::Row;
+
+use crate::{
+    dialect::Dialect,
+    error::CrudError,
+    prelude::{Database, DatabaseFetchAll},
+    router::{Db, Pool},
+};
+
+/// Text search configuration of the Postgres `tsvector` columns and of the queries on them.
+pub const TEXT_SEARCH_CONFIG: &str = "english";
+
+/// Column types a resource field can map to, spelled per dialect by [`SqlType::sql`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlType {
+    BigInt,
+    Integer,
+    Double,
+    Boolean,
+    Text,
+}
+
+impl SqlType {
+    /// The type as written in `CREATE TABLE` for `dialect`.
+    pub fn sql(self, dialect: Dialect) -> &'static str {
+        match (self, dialect) {
+            (SqlType::BigInt, _) => "bigint",
+            (SqlType::Integer, Dialect::MySql) => "int",
+            (SqlType::Integer, _) => "integer",
+            (SqlType::Double, Dialect::MySql) => "double",
+            (SqlType::Double, _) => "double precision",
+            (SqlType::Boolean, _) => "boolean",
+            (SqlType::Text, Dialect::MySql) => "varchar(255)",
+            (SqlType::Text, _) => "text",
+        }
+    }
+
+    /// Whether a live column type, as the catalog of `dialect` reports it, stores this type.
+    fn matches(self, dialect: Dialect, live: &str) -> bool {
+        let live = live.to_lowercase();
+        let live = live.split('(').next().unwrap_or_default().trim();
+
+        let aliases: &[&str] = match self {
+            SqlType::BigInt => &["bigint", "int8"],
+            SqlType::Integer => &["integer", "int", "int4"],
+            SqlType::Double => &["double precision", "double", "float8", "real"],
+            SqlType::Boolean => &["boolean", "bool", "tinyint"],
+            SqlType::Text => &["text", "varchar", "character varying"],
+        };
+
+        aliases.contains(&live) || (dialect == Dialect::Sqlite && live == self.sql(dialect))
+    }
+}
+
+/// Maps a field type to its column type, `Option` making the column nullable.
+pub trait ColumnType {
+    const SQL_TYPE: SqlType;
+    const NULLABLE: bool = false;
+}
+
+impl ColumnType for i64 {
+    const SQL_TYPE: SqlType = SqlType::BigInt;
+}
+
+impl ColumnType for i32 {
+    const SQL_TYPE: SqlType = SqlType::Integer;
+}
+
+impl ColumnType for f64 {
+    const SQL_TYPE: SqlType = SqlType::Double;
+}
+
+impl ColumnType for bool {
+    const SQL_TYPE: SqlType = SqlType::Boolean;
+}
+
+impl ColumnType for String {
+    const SQL_TYPE: SqlType = SqlType::Text;
+}
+
+impl<T: ColumnType> ColumnType for Option<T> {
+    const SQL_TYPE: SqlType = T::SQL_TYPE;
+    const NULLABLE: bool = true;
+}
+
+/// Column a foreign key points at.
+#[derive(Debug, Clone, Copy)]
+pub struct Reference {
+    pub table: &'static str,
+    pub column: &'static str,
+}
+
+/// A stored column of a resource, skipped fields are not part of the table.
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub name: &'static str,
+    pub sql_type: SqlType,
+    pub nullable: bool,
+    pub primary_key: bool,
+    /// The parent's id, for the field marked as parent.
+    pub references: Option<Reference>,
+}
+
+impl Column {
+    fn definition(&self, dialect: Dialect) -> String {
+        let mut sql = format!("{} {}", self.name, self.sql_type.sql(dialect));
+
+        if self.primary_key {
+            sql.push_str(" PRIMARY KEY");
+        } else if !self.nullable {
+            sql.push_str(" NOT NULL");
+        }
+
+        // MySQL parses but ignores inline references, the constraint goes after the columns.
+        if let (Some(reference), false) = (self.references, dialect == Dialect::MySql) {
+            sql.push_str(&format!(
+                " REFERENCES {} ({})",
+                reference.table, reference.column
+            ));
+        }
+
+        sql
+    }
+}
+
+/// The table of a resource, as its `Database` impl describes it.
+#[derive(Debug, Clone, Copy)]
+pub struct Table {
+    pub name: &'static str,
+    pub columns: &'static [Column],
+    /// Text columns the full-text index covers.
+    pub text: &'static [&'static str],
+    pub full_text_table: &'static str,
+    pub full_text_column: &'static str,
+}
+
+impl Table {
+    pub fn of<T: Database<Db> + DatabaseFetchAll<Db>>() -> Self {
+        Self {
+            name: T::TABLE,
+            columns: T::COLUMNS,
+            text: T::FIELDS_TEXT,
+            full_text_table: T::FULL_TEXT_TABLE,
+            full_text_column: T::FULL_TEXT_COLUMN,
+        }
+    }
+
+    /// `CREATE TABLE` statement for `dialect`, followed by an index per foreign key where the
+    /// dialect does not create one itself.
+    ///
+    /// The full-text index comes with what keeps it current: on SQLite an external content
+    /// FTS5 table and the triggers copying every write to it, on Postgres a generated
+    /// `tsvector` column and its GIN index.
+    pub fn create(&self, dialect: Dialect) -> String {
+        let mut definitions = self
+            .columns
+            .iter()
+            .map(|column| column.definition(dialect))
+            .collect::<Vec<_>>();
+
+        if dialect == Dialect::Postgres && !self.full_text_column.is_empty() {
+            let document = self
+                .text
+                .iter()
+                .map(|name| format!("coalesce({name}, '')"))
+                .collect::<Vec<_>>();
+            definitions.push(format!(
+                "{} tsvector GENERATED ALWAYS AS (to_tsvector('{TEXT_SEARCH_CONFIG}', {})) STORED",
+                self.full_text_column,
+                document.join(" || ' ' || ")
+            ));
+        }
+
+        let references = self
+            .columns
+            .iter()
+            .filter_map(|column| Some((column.name, column.references?)))
+            .collect::<Vec<_>>();
+
+        if dialect == Dialect::MySql {
+            definitions.extend(references.iter().map(|(name, reference)| {
+                format!(
+                    "FOREIGN KEY ({name}) REFERENCES {} ({})",
+                    reference.table, reference.column
+                )
+            }));
+        }
+
+        let mut sql = format!(
+            "CREATE TABLE {} (\n    {}\n);\n",
+            self.name,
+            definitions.join(",\n    ")
+        );
+
+        if dialect != Dialect::MySql {
+            for (name, _) in references {
+                sql.push_str(&format!(
+                    "\nCREATE INDEX {table}_{name} ON {table} ({name});\n",
+                    table = self.name
+                ));
+            }
+        }
+
+        match dialect {
+            Dialect::Sqlite if !self.full_text_table.is_empty() => {
+                sql.push_str(&self.full_text_triggers());
+            }
+            Dialect::Postgres if !self.full_text_column.is_empty() => {
+                sql.push_str(&format!(
+                    "\nCREATE INDEX {table}_{column} ON {table} USING gin ({column});\n",
+                    table = self.name,
+                    column = self.full_text_column
+                ));
+            }
+            _ => {}
+        }
+
+        sql
+    }
+
+    /// FTS5 table reading its rows from this one, and the triggers indexing them on insert
+    /// and update and dropping them on update and delete.
+    fn full_text_triggers(&self) -> String {
+        let (table, fts, id) = (self.name, self.full_text_table, self.id());
+        let columns = self.text.join(", ");
+        let values = |row: &str| {
+            std::iter::once(format!("{row}.{id}"))
+                .chain(self.text.iter().map(|name| format!("{row}.{name}")))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let insert = format!(
+            "INSERT INTO {fts} (rowid, {columns}) VALUES ({});",
+            values("new")
+        );
+        let remove = format!(
+            "INSERT INTO {fts} ({fts}, rowid, {columns}) VALUES ('delete', {});",
+            values("old")
+        );
+
+        format!(
+            "\nCREATE VIRTUAL TABLE {fts} USING fts5({columns}, content='{table}', content_rowid='{id}');\n\
+             \nCREATE TRIGGER {fts}_insert AFTER INSERT ON {table} BEGIN\n    {insert}\nEND;\n\
+             \nCREATE TRIGGER {fts}_update AFTER UPDATE ON {table} BEGIN\n    {remove}\n    {insert}\nEND;\n\
+             \nCREATE TRIGGER {fts}_delete AFTER DELETE ON {table} BEGIN\n    {remove}\nEND;\n"
+        )
+    }
+
+    fn id(&self) -> &'static str {
+        self.columns
+            .iter()
+            .find(|column| column.primary_key)
+            .map_or("rowid", |column| column.name)
+    }
+}
+
+/// Compares the live schema with `tables` and returns one line per difference, empty when
+/// they match.
+///
+/// Only the described columns are checked, by type and nullability; extra live columns and
+/// the constraints are left alone.
+pub async fn verify(pool: &Pool, tables: &[Table]) -> Result<Vec<String>, CrudError> {
+    let mut conn = pool.acquire().await?;
+    let dialect = Dialect::from_connection(&conn);
+
+    let sql = match dialect {
+        Dialect::Sqlite => {
+            "SELECT name, type, CASE WHEN \"notnull\" = 0 THEN 'YES' ELSE 'NO' END FROM pragma_table_info(?)"
+        }
+        Dialect::Postgres => {
+            "SELECT column_name::text, data_type::text, is_nullable::text FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = $1"
+        }
+        Dialect::MySql => {
+            "SELECT column_name, data_type, is_nullable FROM information_schema.columns WHERE table_schema = DATABASE() AND table_name = ?"
+        }
+    };
+
+    let mut diff = vec![];
+
+    for table in tables {
+        let live = sqlx::query(sql)
+            .bind(table.name)
+            .fetch_all(&mut *conn)
+            .await?
+            .iter()
+            .map(|row| {
+                Ok((
+                    row.try_get::<String, _>(0)?,
+                    row.try_get::<String, _>(1)?,
+                    row.try_get::<String, _>(2)? == "YES",
+                ))
+            })
+            .collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+        if live.is_empty() {
+            diff.push(format!("{}: missing table", table.name));
+            continue;
+        }
+
+        for column in table.columns {
+            let expected = column.sql_type.sql(dialect);
+            let nullability = |nullable| if nullable { "NULL" } else { "NOT NULL" };
+
+            let Some((_, ty, nullable)) = live.iter().find(|(name, _, _)| name == column.name)
+            else {
+                diff.push(format!(
+                    "{}.{}: missing column, expected {expected} {}",
+                    table.name,
+                    column.name,
+                    nullability(column.nullable)
+                ));
+                continue;
+            };
+
+            if !column.sql_type.matches(dialect, ty) {
+                diff.push(format!(
+                    "{}.{}: expected type {expected}, found {ty}",
+                    table.name, column.name
+                ));
+            }
+
+            // Primary keys are implicitly not null, whatever SQLite reports.
+            if !column.primary_key && column.nullable != *nullable {
+                diff.push(format!(
+                    "{}.{}: expected {}, found {}",
+                    table.name,
+                    column.name,
+                    nullability(column.nullable),
+                    nullability(*nullable)
+                ));
+            }
+        }
+    }
+
+    Ok(diff)
+}
+
+/// Runs the `schema` subcommand, printing the DDL of `tables` for the dialect named `dialect`.
+pub fn command(tables: &[Table], dialect: &str) -> Result<(), CrudError> {
+    let dialect = match dialect {
+        "sqlite" => Dialect::Sqlite,
+        "postgres" => Dialect::Postgres,
+        "mysql" => Dialect::MySql,
+        _ => {
+            return Err(CrudError::InvalidQuery(format!(
+                "Unknown dialect `{dialect}`, expected sqlite, postgres or mysql"
+            )))
+        }
+    };
+
+    let ddl = tables
+        .iter()
+        .map(|table| table.create(dialect))
+        .collect::<Vec<_>>();
+    print!("{}", ddl.join("\n"));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::any::AnyPoolOptions;
+
+    use super::*;
+    use crate::types::{dummy::Dummy, sub_dummy::SubDummy};
+
+    async fn database() -> Pool {
+        sqlx::any::install_default_drivers();
+        AnyPoolOptions::new()
+            .max_connections(1) // needs to be 1, otherwise memory database is gone
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn schema_create_table() {
+        let table = Table::of::<SubDummy>();
+
+        assert_eq!(
+            table.create(Dialect::Sqlite),
+            "CREATE TABLE sub_dummy (\n    id_sub_dummy bigint PRIMARY KEY,\n    id_dummy bigint NOT NULL REFERENCES dummy (id_dummy),\n    name text NOT NULL\n);\n\nCREATE INDEX sub_dummy_id_dummy ON sub_dummy (id_dummy);\n"
+        );
+        assert_eq!(
+            table.create(Dialect::MySql),
+            "CREATE TABLE sub_dummy (\n    id_sub_dummy bigint PRIMARY KEY,\n    id_dummy bigint NOT NULL,\n    name varchar(255) NOT NULL,\n    FOREIGN KEY (id_dummy) REFERENCES dummy (id_dummy)\n);\n"
+        );
+
+        // Every dialect reads the soft delete column back through the Any driver.
+        for dialect in [Dialect::Sqlite, Dialect::Postgres, Dialect::MySql] {
+            assert!(Table::of::<Dummy>()
+                .create(dialect)
+                .contains("\n    deleted_at bigint\n"));
+        }
+
+        // The dummy migrations were written from this output.
+        for dialect in [Dialect::Sqlite, Dialect::Postgres, Dialect::MySql] {
+            assert_eq!(
+                Table::of::<Dummy>().create(dialect),
+                crate::migrate::migrations(dialect)[0].up
+            );
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, crate::prelude::Crud)]
+    #[crud(
+        table = "note",
+        id = "id_note",
+        full_text_table = "note_fts",
+        full_text_column = "search"
+    )]
+    struct Note {
+        id_note: i64,
+        #[crud(search_text)]
+        title: String,
+        #[crud(search_text)]
+        body: Option<String>,
+    }
+
+    #[test]
+    fn schema_create_full_text() {
+        let table = Table::of::<Note>();
+
+        assert_eq!(
+            table.create(Dialect::Postgres),
+            "CREATE TABLE note (\n    id_note bigint PRIMARY KEY,\n    title text NOT NULL,\n    body text,\n    search tsvector GENERATED ALWAYS AS (to_tsvector('english', coalesce(title, '') || ' ' || coalesce(body, ''))) STORED\n);\n\nCREATE INDEX note_search ON note USING gin (search);\n"
+        );
+        assert_eq!(
+            table.create(Dialect::Sqlite),
+            "CREATE TABLE note (\n    id_note bigint PRIMARY KEY,\n    title text NOT NULL,\n    body text\n);\n\n\
+             CREATE VIRTUAL TABLE note_fts USING fts5(title, body, content='note', content_rowid='id_note');\n\n\
+             CREATE TRIGGER note_fts_insert AFTER INSERT ON note BEGIN\n    INSERT INTO note_fts (rowid, title, body) VALUES (new.id_note, new.title, new.body);\nEND;\n\n\
+             CREATE TRIGGER note_fts_update AFTER UPDATE ON note BEGIN\n    INSERT INTO note_fts (note_fts, rowid, title, body) VALUES ('delete', old.id_note, old.title, old.body);\n    INSERT INTO note_fts (rowid, title, body) VALUES (new.id_note, new.title, new.body);\nEND;\n\n\
+             CREATE TRIGGER note_fts_delete AFTER DELETE ON note BEGIN\n    INSERT INTO note_fts (note_fts, rowid, title, body) VALUES ('delete', old.id_note, old.title, old.body);\nEND;\n"
+        );
+        assert!(!table.create(Dialect::MySql).contains("search"));
+    }
+
+    #[tokio::test]
+    async fn schema_verify() {
+        let pool = database().await;
+        let tables = [Table::of::<Dummy>(), Table::of::<SubDummy>()];
+
+        assert_eq!(
+            verify(&pool, &tables).await.unwrap(),
+            ["dummy: missing table", "sub_dummy: missing table"]
+        );
+
+        crate::migrate::up(&pool).await.unwrap();
+
+        assert!(verify(&pool, &tables).await.unwrap().is_empty());
+
+        sqlx::raw_sql(
+            "DROP TABLE sub_dummy; CREATE TABLE sub_dummy (id_sub_dummy bigint PRIMARY KEY, id_dummy text, extra text);",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            verify(&pool, &tables).await.unwrap(),
+            [
+                "sub_dummy.id_dummy: expected type bigint, found TEXT",
+                "sub_dummy.id_dummy: expected NOT NULL, found NULL",
+                "sub_dummy.name: missing column, expected text NOT NULL",
+            ]
+        );
+    }
+
+    #[test]
+    fn schema_command_unknown() {
+        assert!(matches!(
+            command(&[], "oracle"),
+            Err(CrudError::InvalidQuery(_))
+        ));
+    }
+}
@@ -0,0 +1,316 @@
+use std::{iter::Peekable, str::Chars};
+
+use crate::{
+    dialect::{Dialect, Placeholders},
+    error::CrudError,
+    prelude::QueryToken,
+};
+
+/// Searchable columns of a type, by the kind of value they are compared with.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchFields {
+    pub text: &'static [&'static str],
+    pub numeric: &'static [&'static str],
+    pub float: &'static [&'static str],
+}
+
+/// One term of a search query: matches a row when any of its `matches` does, or when none
+/// does if `negated`. `value` is the term as typed, for full-text queries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchTerm {
+    pub negated: bool,
+    pub scoped: bool,
+    pub value: String,
+    pub matches: Vec<(&'static str, QueryToken)>,
+}
+
+impl SearchTerm {
+    /// Parses a search query into terms a row has to match all of.
+    ///
+    /// Terms are separated by whitespace. `"new york"` is a single phrase, `-term` excludes
+    /// rows matching `term` and `field:value` only looks at `field`. A term without a field
+    /// is tried on every text column, and on the numeric and float columns when it parses as
+    /// a number.
+    pub fn parse(fields: SearchFields, query: &str) -> Result<Vec<Self>, CrudError> {
+        let mut chars = query.chars().peekable();
+        let mut terms = vec![];
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+            if chars.peek().is_none() {
+                break;
+            }
+
+            let negated = chars.next_if_eq(&'-').is_some();
+            let (field, value) = Self::read(&mut chars)?;
+
+            terms.push(Self::term(fields, negated, field, value)?);
+        }
+
+        Ok(terms)
+    }
+
+    fn read(chars: &mut Peekable<Chars>) -> Result<(Option<String>, String), CrudError> {
+        if chars.peek() == Some(&'"') {
+            return Ok((None, Self::read_phrase(chars)?));
+        }
+
+        let mut field = None;
+        let mut word = String::new();
+
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+            if c == ':' && field.is_none() && is_identifier(&word) {
+                field = Some(std::mem::take(&mut word));
+
+                if chars.peek() == Some(&'"') {
+                    return Ok((field, Self::read_phrase(chars)?));
+                }
+            } else {
+                word.push(c);
+            }
+        }
+
+        if word.is_empty() {
+            return Err(CrudError::InvalidQuery(
+                "Search terms must not be empty".to_string(),
+            ));
+        }
+
+        Ok((field, word))
+    }
+
+    fn read_phrase(chars: &mut Peekable<Chars>) -> Result<String, CrudError> {
+        chars.next();
+
+        let mut phrase = String::new();
+
+        loop {
+            match chars.next() {
+                Some('"') if phrase.is_empty() => {
+                    return Err(CrudError::InvalidQuery(
+                        "Search terms must not be empty".to_string(),
+                    ))
+                }
+                Some('"') => return Ok(phrase),
+                Some(c) => phrase.push(c),
+                None => {
+                    return Err(CrudError::InvalidQuery(
+                        "Unterminated quote in search".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    fn term(
+        fields: SearchFields,
+        negated: bool,
+        field: Option<String>,
+        value: String,
+    ) -> Result<Self, CrudError> {
+        let find = |names: &'static [&'static str]| {
+            names
+                .iter()
+                .copied()
+                .filter(|name| field.as_deref().is_none_or(|field| *name == field))
+                .collect::<Vec<_>>()
+        };
+
+        let (text, numeric, float) = (find(fields.text), find(fields.numeric), find(fields.float));
+
+        if let Some(field) = &field {
+            if text.is_empty() && numeric.is_empty() && float.is_empty() {
+                return Err(CrudError::InvalidQuery(format!(
+                    "Unknown search field `{field}`"
+                )));
+            }
+        }
+
+        let mut matches = vec![];
+
+        if let Ok(number) = value.parse::<f64>() {
+            matches.extend(float.iter().map(|f| (*f, QueryToken::Float(number))));
+        }
+        if let Ok(number) = value.parse::<i64>() {
+            matches.extend(numeric.iter().map(|f| (*f, QueryToken::Numeric(number))));
+        }
+        matches.extend(
+            text.iter()
+                .map(|f| (*f, QueryToken::Text(format!("%{value}%")))),
+        );
+
+        if let (Some(field), true) = (&field, matches.is_empty()) {
+            return Err(CrudError::InvalidQuery(format!(
+                "Invalid value `{value}` for search field `{field}`"
+            )));
+        }
+
+        Ok(Self {
+            negated,
+            scoped: field.is_some(),
+            value,
+            matches,
+        })
+    }
+
+    /// Full-text query for the terms without a field: FTS5 syntax on SQLite, the
+    /// `websearch_to_tsquery` one on Postgres. Terms are quoted so they can't be read as
+    /// operators. `None` when no term is left or all of them are negated.
+    pub fn full_text(terms: &[Self], dialect: Dialect) -> Option<String> {
+        let terms = terms.iter().filter(|t| !t.scoped).collect::<Vec<_>>();
+
+        if terms.iter().all(|t| t.negated) {
+            return None;
+        }
+
+        let query = match dialect {
+            Dialect::Postgres => terms
+                .iter()
+                .map(|t| {
+                    let value = t.value.replace('"', " ");
+                    match t.negated {
+                        true => format!("-\"{value}\""),
+                        false => format!("\"{value}\""),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            Dialect::Sqlite | Dialect::MySql => {
+                let quoted = |t: &&Self| format!("\"{}\"", t.value.replace('"', "\"\""));
+                let positive = terms.iter().filter(|t| !t.negated).map(quoted);
+                let negative = terms.iter().filter(|t| t.negated).map(quoted);
+
+                positive.collect::<Vec<_>>().join(" AND ")
+                    + &negative.map(|t| format!(" NOT {t}")).collect::<String>()
+            }
+        };
+
+        Some(query)
+    }
+
+    /// A term no column can match, e.g. a word on a type without text columns, matches no
+    /// row, and so every row when negated.
+    pub fn sql(&self, placeholders: &mut Placeholders) -> String {
+        let matches = self
+            .matches
+            .iter()
+            .map(|(field, token)| match token {
+                QueryToken::Text(_) => format!("{field} LIKE {}", placeholders.bind()),
+                _ => format!("{field} = {}", placeholders.bind()),
+            })
+            .collect::<Vec<_>>();
+
+        match (self.negated, matches.is_empty()) {
+            (false, true) => "1 = 0".to_string(),
+            (true, true) => "1 = 1".to_string(),
+            (false, false) => format!("({})", matches.join(" OR ")),
+            (true, false) => format!("NOT ({})", matches.join(" OR ")),
+        }
+    }
+}
+
+fn is_identifier(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: SearchFields = SearchFields {
+        text: &["title", "name"],
+        numeric: &["id"],
+        float: &["lat"],
+    };
+
+    #[test]
+    fn search_parse() {
+        let terms =
+            SearchTerm::parse(FIELDS, r#" "new york" -closed name:"a b" id:3 1.5 "#).unwrap();
+
+        assert_eq!(
+            terms
+                .iter()
+                .map(|t| (t.negated, t.scoped, t.value.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (false, false, "new york"),
+                (true, false, "closed"),
+                (false, true, "a b"),
+                (false, true, "3"),
+                (false, false, "1.5"),
+            ]
+        );
+        assert_eq!(
+            terms[0].matches,
+            [
+                ("title", QueryToken::Text("%new york%".to_string())),
+                ("name", QueryToken::Text("%new york%".to_string())),
+            ]
+        );
+        assert_eq!(terms[3].matches, [("id", QueryToken::Numeric(3))]);
+        assert_eq!(
+            terms[4].matches,
+            [
+                ("lat", QueryToken::Float(1.5)),
+                ("title", QueryToken::Text("%1.5%".to_string())),
+                ("name", QueryToken::Text("%1.5%".to_string())),
+            ]
+        );
+
+        let mut placeholders = Dialect::Postgres.placeholders();
+        let sql = terms
+            .iter()
+            .map(|t| t.sql(&mut placeholders))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            sql,
+            [
+                "(title LIKE $1 OR name LIKE $2)",
+                "NOT (title LIKE $3 OR name LIKE $4)",
+                "(name LIKE $5)",
+                "(id = $6)",
+                "(lat = $7 OR title LIKE $8 OR name LIKE $9)",
+            ]
+        );
+    }
+
+    #[test]
+    fn search_full_text() {
+        let terms = SearchTerm::parse(FIELDS, r#"-closed "new york" name:x say"hi"#).unwrap();
+
+        assert_eq!(
+            SearchTerm::full_text(&terms, Dialect::Sqlite).as_deref(),
+            Some(r#""new york" AND "say""hi" NOT "closed""#)
+        );
+        assert_eq!(
+            SearchTerm::full_text(&terms, Dialect::Postgres).as_deref(),
+            Some(r#"-"closed" "new york" "say hi""#)
+        );
+        assert_eq!(SearchTerm::full_text(&terms[..1], Dialect::Sqlite), None);
+    }
+
+    #[test]
+    fn search_invalid() {
+        for query in [
+            "\"open", "-", "a - b", "name:", "\"\"", "size:3", "id:three",
+        ] {
+            assert!(
+                matches!(
+                    SearchTerm::parse(FIELDS, query),
+                    Err(CrudError::InvalidQuery(_))
+                ),
+                "{query}"
+            );
+        }
+
+        assert_eq!(SearchTerm::parse(FIELDS, "  ").unwrap(), []);
+        assert_eq!(
+            SearchTerm::parse(FIELDS, "12:30").unwrap()[0].matches[0],
+            ("title", QueryToken::Text("%12:30%".to_string()))
+        );
+    }
+}
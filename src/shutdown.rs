@@ -0,0 +1,198 @@
+use std::{
+    future::{Future, IntoFuture},
+    io,
+    time::Duration,
+};
+
+use axum::Router;
+use tokio::{net::TcpListener, sync::oneshot};
+
+use crate::health::Readiness;
+
+/// Resolves on SIGINT (Ctrl-C) or, on Unix, SIGTERM.
+pub async fn signal() {
+    let interrupt = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+/// Serves `app` until `signal` resolves, then clears `readiness` at once and keeps serving
+/// for `pre_stop`, giving load balancers time to notice. Only then it stops accepting
+/// connections and gives the in-flight requests up to `drain` to finish.
+///
+/// Returns whether every request finished in time; the remaining ones are dropped.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    readiness: Readiness,
+    pre_stop: Duration,
+    drain: Duration,
+    signal: impl Future<Output = ()> + Send + 'static,
+) -> io::Result<bool> {
+    let (begun, shutdown) = oneshot::channel();
+
+    readiness.set(true);
+    let mut server = tokio::spawn(
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async move {
+                signal.await;
+                readiness.set(false);
+                if !pre_stop.is_zero() {
+                    tracing::info!("Shutdown requested, still serving for {pre_stop:?}");
+                    tokio::time::sleep(pre_stop).await;
+                }
+                let _ = begun.send(());
+            })
+            .into_future(),
+    );
+
+    // The sender is dropped unsent when the server stops on its own.
+    if shutdown.await.is_ok() {
+        tracing::info!("Shutting down, draining requests for up to {drain:?}");
+    }
+
+    match tokio::time::timeout(drain, &mut server).await {
+        Ok(result) => result.map_err(io::Error::other)?.map(|()| true),
+        Err(_) => {
+            tracing::warn!("Drain timeout elapsed, dropping the remaining requests");
+            server.abort();
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    /// Starts serving a route answering after `delay` and sends one request to it, returning
+    /// the connection, the readiness, the shutdown trigger and the server.
+    async fn slow_server(
+        delay: Duration,
+        pre_stop: Duration,
+        drain: Duration,
+    ) -> (
+        tokio::net::TcpStream,
+        Readiness,
+        oneshot::Sender<()>,
+        tokio::task::JoinHandle<io::Result<bool>>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let app = Router::new().route(
+            "/slow",
+            get(move || async move {
+                tokio::time::sleep(delay).await;
+                "done"
+            }),
+        );
+
+        let readiness = Readiness::default();
+        let (trigger, signal) = oneshot::channel::<()>();
+        let server = tokio::spawn(serve(
+            listener,
+            app,
+            readiness.clone(),
+            pre_stop,
+            drain,
+            async move {
+                let _ = signal.await;
+            },
+        ));
+
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        // Lets the request reach the handler before shutdown begins.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        (stream, readiness, trigger, server)
+    }
+
+    #[tokio::test]
+    async fn shutdown_drains_requests() {
+        let (mut stream, readiness, trigger, server) = slow_server(
+            Duration::from_millis(200),
+            Duration::ZERO,
+            Duration::from_secs(5),
+        )
+        .await;
+        assert!(readiness.is_ready());
+
+        trigger.send(()).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!readiness.is_ready());
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("done"));
+
+        assert!(server.await.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn shutdown_drain_timeout() {
+        let (_stream, readiness, trigger, server) = slow_server(
+            Duration::from_secs(10),
+            Duration::ZERO,
+            Duration::from_millis(100),
+        )
+        .await;
+
+        trigger.send(()).unwrap();
+
+        assert!(!server.await.unwrap().unwrap());
+        assert!(!readiness.is_ready());
+    }
+
+    #[tokio::test]
+    async fn shutdown_pre_stop_delay() {
+        let (stream, readiness, trigger, server) = slow_server(
+            Duration::from_millis(10),
+            Duration::from_millis(300),
+            Duration::from_secs(5),
+        )
+        .await;
+        let address = stream.peer_addr().unwrap();
+
+        trigger.send(()).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!readiness.is_ready());
+
+        // Not ready any more, but new connections are still served during the delay.
+        let mut late = tokio::net::TcpStream::connect(address).await.unwrap();
+        late.write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        late.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(!server.is_finished());
+
+        assert!(server.await.unwrap().unwrap());
+    }
+}